@@ -0,0 +1,84 @@
+//! Compares spawning `git status` per worktree against the native libgit2 status.
+//!
+//! Run with `cargo bench --bench status`.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const WORKTREE_COUNT: usize = 50;
+const FILES_PER_WORKTREE: usize = 200;
+const ITERATIONS: u32 = 5;
+
+fn git(dir: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute git");
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+}
+
+fn spawn_git_status(worktrees: &[gwtr::WorktreeInfo]) -> usize {
+    let mut changes = 0;
+    for worktree in worktrees {
+        let output = Command::new("git")
+            .args(["status", "--porcelain"])
+            .current_dir(&worktree.path)
+            .output()
+            .expect("Failed to execute git status");
+        changes += String::from_utf8_lossy(&output.stdout).lines().count();
+    }
+    changes
+}
+
+fn native_status(repo: &git2::Repository) -> usize {
+    gwtr::collect_worktree_statuses(repo, gwtr::UntrackedFiles::Normal)
+        .expect("Failed to collect statuses")
+        .into_iter()
+        .map(|(_, status)| status.unwrap_or(0))
+        .sum()
+}
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let mut changes = f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        changes = f();
+    }
+    (start.elapsed() / ITERATIONS, changes)
+}
+
+fn main() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let repo_path = temp_dir.path().join("bench");
+    std::fs::create_dir(&repo_path).unwrap();
+    
+    git(&repo_path, &["init", "-q"]);
+    git(&repo_path, &["config", "user.email", "bench@example.com"]);
+    git(&repo_path, &["config", "user.name", "Bench"]);
+    for i in 0..FILES_PER_WORKTREE {
+        std::fs::write(repo_path.join(format!("file-{}.txt", i)), format!("content {}", i)).unwrap();
+    }
+    git(&repo_path, &["add", "."]);
+    git(&repo_path, &["commit", "-q", "-m", "Initial commit"]);
+    
+    for i in 0..WORKTREE_COUNT {
+        let path = temp_dir.path().join(format!("bench_wt-{}", i));
+        git(&repo_path, &["worktree", "add", "-q", "-b", &format!("wt-{}", i), path.to_str().unwrap()]);
+        // Leave a few changes behind so both approaches have something to report
+        std::fs::write(path.join("file-0.txt"), "modified").unwrap();
+        std::fs::write(path.join("untracked.txt"), "untracked").unwrap();
+    }
+    
+    let repo = git2::Repository::open(&repo_path).unwrap();
+    let worktrees = gwtr::get_worktrees(&repo).unwrap();
+    
+    let (spawned, spawned_changes) = time(|| spawn_git_status(&worktrees));
+    let (native, native_changes) = time(|| native_status(&repo));
+    
+    println!("status of {} worktrees ({} files each), mean of {} runs", worktrees.len(), FILES_PER_WORKTREE, ITERATIONS);
+    println!("  git status per worktree: {:>10.2?} ({} changes)", spawned, spawned_changes);
+    println!("  native libgit2 (pooled): {:>10.2?} ({} changes)", native, native_changes);
+    println!("  speedup: {:.1}x", spawned.as_secs_f64() / native.as_secs_f64());
+}
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result, bail};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::rename::move_worktree;
+use crate::{
+    Metadata, canonical, get_worktree_path, get_worktrees, is_main_worktree, worktree_name,
+};
+
+/// Register a worktree created outside gwtr under a name, optionally moving it
+/// to the path the layout template gives for that name
+pub fn adopt_worktree(repo: &Repository, path: &Path, name: Option<&str>, relocate: bool) -> Result<PathBuf> {
+    let path = path.canonicalize()
+        .with_context(|| format!("Worktree not found at {:?}", path))?;
+    
+    let worktree = get_worktrees(repo)?
+        .into_iter()
+        .find(|worktree| canonical(&worktree.path) == path)
+        .with_context(|| format!("{:?} is not a worktree of this repository", path))?;
+    if worktree.is_bare || is_main_worktree(repo, &path) {
+        bail!("The main worktree cannot be adopted");
+    }
+    
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => worktree_name(repo, &path)?,
+    };
+    
+    let metadata = Metadata::load(repo)?;
+    if let Some(entry) = metadata.worktrees.get(&name)
+        && canonical(&entry.path) != path {
+        bail!("The name '{}' is already used by the worktree at {:?}", name, entry.path);
+    }
+    // The name gwtr already tracks the worktree under, if any
+    let previous_name = metadata.entry_for_path(&path)
+        .map(|(previous_name, _)| previous_name.to_string());
+    
+    let mut final_path = path.clone();
+    if relocate {
+        let new_path = get_worktree_path(repo, &name)?;
+        if new_path.exists() {
+            bail!("Cannot move the worktree to {:?}: it already exists", new_path);
+        }
+        move_worktree(repo, previous_name.as_deref().unwrap_or(&name), &path, &name, &new_path, None)?;
+        final_path = new_path;
+    }
+    
+    // Move the entry to the new name, keeping its ports and other settings
+    Metadata::update(repo, |metadata| {
+        let mut entry = previous_name.as_ref()
+            .and_then(|previous_name| metadata.worktrees.remove(previous_name))
+            .or_else(|| metadata.worktrees.remove(&name))
+            .unwrap_or_default();
+        // Drop any other name the worktree was registered under
+        metadata.worktrees.retain(|_, other| canonical(&other.path) != path && canonical(&other.path) != final_path);
+        entry.path = final_path.clone();
+        entry.branch = worktree.branch.clone();
+        metadata.worktrees.insert(name.clone(), entry);
+    })?;
+    
+    println!("Adopted worktree at {:?} as '{}'", final_path, name);
+    if relocate {
+        println!("cd '{}'", final_path.display());
+    }
+    
+    Ok(final_path)
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use std::path::Path;
+use std::process::Command;
+
+use crate::detect_merge;
+
+fn git_config(dir: &Path, key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    
+    if !output.status.success() {
+        return None;
+    }
+    
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Delete a local branch after its worktree was removed
+///
+/// Branches merged into `base` (by any [`crate::MergeRule`]) are deleted; unmerged
+/// branches are refused unless `force` is set. With `delete_remote`, the branch
+/// the local branch tracks is deleted on its remote as well.
+pub fn delete_branch(dir: &Path, branch: &str, base: &str, force: bool, delete_remote: bool) -> Result<()> {
+    let rule = detect_merge(dir, branch, base).ok().flatten();
+    
+    if rule.is_none() && !force {
+        bail!("Branch '{}' is not merged into '{}'; use --force to delete it anyway", branch, base);
+    }
+    
+    // Read the upstream before the branch and its configuration are gone
+    let upstream = git_config(dir, &format!("branch.{}.remote", branch))
+        .zip(git_config(dir, &format!("branch.{}.merge", branch)));
+    
+    // The merge into `base` was verified above. `git branch -d` would check against
+    // the current HEAD instead, which is not `base` when run from another worktree
+    let output = Command::new("git")
+        .args(["branch", "-D", branch])
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git branch command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to delete branch '{}': {}", branch, stderr.trim());
+    }
+    
+    println!("Deleted branch '{}'", branch.cyan());
+    
+    if !delete_remote {
+        return Ok(());
+    }
+    
+    let Some((remote, merge_ref)) = upstream.filter(|(remote, _)| remote != ".") else {
+        println!("Branch '{}' has no remote branch to delete", branch.cyan());
+        return Ok(());
+    };
+    let remote_branch = merge_ref.strip_prefix("refs/heads/").unwrap_or(&merge_ref);
+    
+    let output = Command::new("git")
+        .args(["push", &remote, "--delete", remote_branch])
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git push command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to delete remote branch '{}/{}': {}", remote, remote_branch, stderr.trim());
+    }
+    
+    println!("Deleted remote branch '{}/{}'", remote, remote_branch.cyan());
+    
+    Ok(())
+}
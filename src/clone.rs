@@ -0,0 +1,112 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::{CONFIG_FILE_NAME, Config, Layout, Metadata, WorktreeMetadata, config_path, get_worktree_path, git};
+
+/// Directory name git would pick for a clone of `url`, e.g. `app` for `https://host/org/app.git`
+fn directory_for_url(url: &str) -> Result<PathBuf> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .map(|name| name.trim_end_matches(".git"))
+        .filter(|name| !name.is_empty())
+        .with_context(|| format!("Cannot derive a directory name from '{}'; pass one explicitly", url))?;
+    
+    Ok(PathBuf::from(name))
+}
+
+/// Record the remote's default branch as the base branch in `.gwtr.toml`, unless
+/// it is the default base branch anyway
+fn record_base_branch(repo: &Repository, base_branch: &str) -> Result<()> {
+    if base_branch == Config::default().base_branch {
+        return Ok(());
+    }
+    
+    let mut table = toml::Table::new();
+    table.insert("base_branch".to_string(), base_branch.into());
+    let content = toml::to_string(&table).context("Failed to serialize configuration")?;
+    let path = config_path(repo);
+    std::fs::write(&path, format!("# Written by gwtr clone\n{}", content))
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    println!("Wrote {} with base_branch = {}", CONFIG_FILE_NAME, base_branch.cyan());
+    
+    Ok(())
+}
+
+/// Clone `url` into a worktree-centric layout and create the base branch worktree
+///
+/// The layout comes from `layout`, or else from the user-level configuration.
+pub fn clone_repository(url: &str, directory: Option<&Path>, layout: Option<Layout>) -> Result<PathBuf> {
+    let config = Config::load_user()?;
+    let layout = layout.or(config.layout).unwrap_or_default();
+    let directory = match directory {
+        Some(directory) => directory.to_path_buf(),
+        None => directory_for_url(url)?,
+    };
+    
+    if directory.exists() && directory.read_dir().map(|mut entries| entries.next().is_some()).unwrap_or(true) {
+        bail!("Destination {:?} already exists and is not empty", directory);
+    }
+    
+    let current_dir = std::env::current_dir()?;
+    let directory_str = directory.to_string_lossy().to_string();
+    
+    match layout {
+        Layout::Main => {
+            println!("Cloning {} into {:?}...", url, directory);
+            git(&current_dir, &["clone", url, &directory_str])?;
+            let directory = directory.canonicalize()?;
+            let base_branch = git(&directory, &["branch", "--show-current"])?;
+            let repo = Repository::open(&directory)?;
+            record_base_branch(&repo, &base_branch)?;
+            
+            println!("{}", "Layout (main clone):".bold());
+            println!("  {} (main) [{}]", directory.display().to_string().green(), base_branch.cyan());
+            println!("  new worktrees: {}", get_worktree_path(&repo, "<name>")?.display());
+            println!("cd '{}'", directory.display());
+            
+            Ok(directory)
+        }
+        Layout::Bare => {
+            println!("Cloning {} into {:?} as a bare repository...", url, directory);
+            std::fs::create_dir_all(&directory)
+                .with_context(|| format!("Failed to create {:?}", directory))?;
+            let directory = directory.canonicalize()?;
+            git(&directory, &["clone", "--bare", url, ".bare"])?;
+            std::fs::write(directory.join(".git"), "gitdir: ./.bare\n")
+                .context("Failed to write .git file")?;
+            
+            // A bare clone copies the remote's branches as local branches and sets no
+            // fetch refspec, so fetching would never update remote-tracking branches
+            git(&directory, &["config", "remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*"])?;
+            git(&directory, &["fetch", "origin"])?;
+            git(&directory, &["remote", "set-head", "origin", "--auto"])?;
+            
+            let base_branch = git(&directory, &["symbolic-ref", "--short", "HEAD"])?;
+            let repo = Repository::open(&directory)?;
+            record_base_branch(&repo, &base_branch)?;
+            let worktree_path = get_worktree_path(&repo, &base_branch)?;
+            git(&directory, &["worktree", "add", &worktree_path.to_string_lossy(), &base_branch])?;
+            git(&worktree_path, &["branch", "--set-upstream-to", &format!("origin/{}", base_branch)])?;
+            
+            Metadata::update(&repo, |metadata| {
+                metadata.worktrees.insert(base_branch.clone(), WorktreeMetadata {
+                    path: worktree_path.clone(),
+                    branch: Some(base_branch.clone()),
+                    ..Default::default()
+                });
+            })?;
+            
+            println!("{}", "Layout (bare repository):".bold());
+            println!("  {} [bare]", directory.join(".bare").display().to_string().yellow());
+            println!("  {} [{}]", worktree_path.display().to_string().yellow(), base_branch.cyan());
+            println!("  new worktrees: {}", get_worktree_path(&repo, "<name>")?.display());
+            println!("cd '{}'", worktree_path.display());
+            
+            Ok(worktree_path)
+        }
+    }
+}
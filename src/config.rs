@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::get_project_root;
+
+/// Name of the per-repository configuration file, read from the main worktree
+pub const CONFIG_FILE_NAME: &str = ".gwtr.toml";
+
+/// Name of the user-level configuration file inside [`user_config_dir`]
+pub const USER_CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Default layout: a sibling of the main worktree named `{repo}_{name}`
+pub const DEFAULT_PATH_TEMPLATE: &str = "../{repo}_{name}";
+
+/// Default layout of a bare repository: a directory named `{name}` next to the
+/// git directory, as in `project/.bare` and `project/{name}`
+pub const DEFAULT_BARE_PATH_TEMPLATE: &str = "{name}";
+
+/// How the worktrees of a repository are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// A regular clone that is the main worktree, with linked worktrees next to it
+    #[default]
+    Main,
+    /// A bare repository in `.bare` with every checkout a worktree next to it
+    Bare,
+}
+
+/// The `[tmux]` section of `.gwtr.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TmuxConfig {
+    /// Name of a private tmux server socket, as passed to `tmux -L`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<String>,
+    /// Windows to create in a new session; one plain window if empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TmuxWindow>,
+}
+
+impl TmuxConfig {
+    fn is_empty(&self) -> bool {
+        self == &TmuxConfig::default()
+    }
+}
+
+/// A window of a `gwtr tmux` session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TmuxWindow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Command typed into the window's first pane
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Commands for additional panes, each split off the window
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub panes: Vec<String>,
+    /// tmux layout applied after splitting, e.g. `main-vertical` or `tiled`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+}
+
+/// Repository settings read from `.gwtr.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Branch that worktrees are merged into
+    pub base_branch: String,
+    /// Remote whose default branch `gwtr init` records as the base branch
+    pub remote: String,
+    /// Layout `gwtr clone` creates: a main clone or a bare repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    /// Where worktrees are placed, relative to the main worktree (or the project
+    /// directory of a bare layout); `{repo}` and `{name}` are replaced by the
+    /// repository and worktree names
+    pub path_template: Option<String>,
+    /// Names of ignored directories that `gwtr du --clean-artifacts` deletes
+    pub artifact_patterns: Vec<String>,
+    /// First port handed out to worktrees
+    pub port_base: u16,
+    /// Number of consecutive ports each worktree gets
+    pub port_block_size: u16,
+    /// File inside each new worktree that its environment is written to, e.g. `.env`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Command that `gwtr open` runs, e.g. `code {path}`; defaults to `$VISUAL` or `$EDITOR`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Initialise and check out submodules in every new worktree
+    pub submodules: bool,
+    /// Named lists of directories for `gwtr add --sparse`, checked out in cone mode
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub sparse_profiles: BTreeMap<String, Vec<String>>,
+    /// How long `gwtr tmp` worktrees live when no `--ttl` is given, e.g. `1d`
+    pub tmp_ttl: String,
+    /// Session layout for `gwtr tmux`
+    #[serde(skip_serializing_if = "TmuxConfig::is_empty")]
+    pub tmux: TmuxConfig,
+    /// Delete the local branch after its worktree is removed or pruned
+    pub delete_branch: bool,
+    /// When deleting a branch, also delete the branch it tracks on the remote
+    pub delete_remote_branch: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            base_branch: "main".to_string(),
+            remote: "origin".to_string(),
+            layout: None,
+            path_template: None,
+            artifact_patterns: vec!["target".to_string(), "node_modules".to_string()],
+            port_base: 4000,
+            port_block_size: 10,
+            env_file: None,
+            editor: None,
+            submodules: false,
+            sparse_profiles: BTreeMap::new(),
+            tmp_ttl: "1d".to_string(),
+            tmux: TmuxConfig::default(),
+            delete_branch: false,
+            delete_remote_branch: false,
+        }
+    }
+}
+
+impl Config {
+    /// The configured path template, or the default for the repository layout
+    pub fn path_template(&self, bare_layout: bool) -> &str {
+        match &self.path_template {
+            Some(template) => template,
+            None if bare_layout => DEFAULT_BARE_PATH_TEMPLATE,
+            None => DEFAULT_PATH_TEMPLATE,
+        }
+    }
+    
+    /// Load the configuration for the repository, falling back to defaults if there is none
+    pub fn load(repo: &Repository) -> Result<Config> {
+        let path = config_path(repo);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?}", path))
+    }
+    
+    /// Load the user-level configuration, which supplies defaults for `gwtr clone`
+    pub fn load_user() -> Result<Config> {
+        let Some(path) = user_config_dir().map(|dir| dir.join(USER_CONFIG_FILE_NAME)) else {
+            return Ok(Config::default());
+        };
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?}", path))
+    }
+}
+
+/// The branch `refs/remotes/<remote>/HEAD` points to, if the remote's default branch is known
+pub(crate) fn remote_default_branch(repo: &Repository, remote: &str) -> Option<String> {
+    let reference = repo.find_reference(&format!("refs/remotes/{}/HEAD", remote)).ok()?;
+    let target = reference.symbolic_target()?;
+    target.strip_prefix(&format!("refs/remotes/{}/", remote)).map(str::to_string)
+}
+
+/// Directory of the user-level configuration: `$XDG_CONFIG_HOME/gwtr`, or `~/.config/gwtr`
+pub fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("gwtr"))
+}
+
+/// Location of `.gwtr.toml`: the root of the main worktree, or the project
+/// directory of a bare layout
+pub fn config_path(repo: &Repository) -> PathBuf {
+    get_project_root(repo).join(CONFIG_FILE_NAME)
+}
@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use colored::*;
+use git2::{Repository, Status, StatusOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+
+/// What would be lost by deleting a worktree
+#[derive(Debug, Clone, Default)]
+pub struct DirtyReport {
+    /// Tracked files with staged or unstaged changes, as `git status --short` lines
+    pub changed: Vec<String>,
+    /// Untracked files that are not ignored
+    pub untracked: Vec<String>,
+    /// Ignored files and directories, such as build output
+    pub ignored: Vec<String>,
+    /// `git diff --stat HEAD` output for the tracked changes
+    pub diffstat: String,
+}
+
+impl DirtyReport {
+    /// Whether the worktree has changes that would be lost, ignored files aside
+    pub fn is_dirty(&self) -> bool {
+        !self.changed.is_empty() || !self.untracked.is_empty()
+    }
+    
+    /// Print the files that would be lost
+    pub fn print(&self) {
+        for line in &self.changed {
+            println!("    {}", line.yellow());
+        }
+        for file in &self.untracked {
+            println!("    {} {}", "??".red(), file.red());
+        }
+        if !self.diffstat.is_empty() {
+            for line in self.diffstat.lines() {
+                println!("   {}", line);
+            }
+        }
+        if !self.ignored.is_empty() {
+            println!("    {} ignored file{} (e.g. {}) will be deleted as well",
+                     self.ignored.len(),
+                     if self.ignored.len() == 1 { "" } else { "s" },
+                     self.ignored[0]);
+        }
+    }
+}
+
+fn status_code(status: Status) -> String {
+    let index = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    
+    let worktree = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_renamed() {
+        'R'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else if status.is_conflicted() {
+        'U'
+    } else {
+        ' '
+    };
+    
+    format!("{}{}", index, worktree)
+}
+
+/// Index flag of files that a sparse checkout leaves out of the worktree
+pub(crate) const SKIP_WORKTREE: u16 = 1 << 14;
+
+/// Whether a status entry only reports a file that a sparse checkout left out
+///
+/// libgit2 does not know about sparse checkouts and reports such files as deleted.
+pub(crate) fn is_sparse_omission(index: &git2::Index, entry: &git2::StatusEntry) -> bool {
+    entry.status() == Status::WT_DELETED
+        && index.get_path(Path::new(entry.path().unwrap_or_default()), 0)
+            .is_some_and(|index_entry| index_entry.flags_extended & SKIP_WORKTREE != 0)
+}
+
+/// Collect the changes, untracked files and ignored files of the worktree at `path`
+pub fn dirty_report(path: &Path) -> Result<DirtyReport> {
+    let worktree_repo = Repository::open(path)
+        .with_context(|| format!("Failed to open worktree at {:?}", path))?;
+    
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false);
+    
+    let statuses = worktree_repo.statuses(Some(&mut options))
+        .with_context(|| format!("Failed to compute status of {:?}", path))?;
+    
+    let index = worktree_repo.index()?;
+    let mut report = DirtyReport::default();
+    for entry in statuses.iter().filter(|entry| !is_sparse_omission(&index, entry)) {
+        let file = entry.path().unwrap_or("<non-UTF-8 path>").to_string();
+        let status = entry.status();
+        
+        if status.is_ignored() {
+            report.ignored.push(file);
+        } else if status == Status::WT_NEW {
+            report.untracked.push(file);
+        } else {
+            report.changed.push(format!("{} {}", status_code(status), file));
+        }
+    }
+    
+    if !report.changed.is_empty() {
+        let output = Command::new("git")
+            .args(["diff", "--stat", "HEAD"])
+            .current_dir(path)
+            .output()
+            .context("Failed to execute git diff command")?;
+        
+        if output.status.success() {
+            report.diffstat = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+        }
+    }
+    
+    Ok(report)
+}
+
+/// Ask the user to confirm on an interactive terminal; never confirms otherwise
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
@@ -0,0 +1,288 @@
+use anyhow::Result;
+use colored::*;
+use git2::Repository;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::{Config, Metadata, canonical, get_command_dir, get_common_dir, get_worktrees, git, layout_pattern};
+
+/// A problem found by `gwtr doctor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// git considers the entry stale, e.g. because its directory was deleted
+    Prunable { path: PathBuf, reason: String, locked: bool },
+    /// The worktree's `.git` file does not point back to its entry in the repository
+    BrokenGitFile { path: PathBuf },
+    /// A directory that was moved away from its registered path
+    Moved { from: PathBuf, to: PathBuf },
+    /// A directory in the worktree layout that git does not know about
+    Orphan { path: PathBuf },
+    /// A branch checked out in more than one worktree
+    CheckedOutTwice { branch: String, paths: Vec<PathBuf> },
+    /// The configured base branch does not exist
+    MissingBaseBranch { branch: String },
+}
+
+impl Problem {
+    /// Whether `gwtr repair` can fix the problem
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            Problem::Prunable { locked, .. } => !locked,
+            Problem::BrokenGitFile { .. } | Problem::Moved { .. } => true,
+            Problem::Orphan { .. } | Problem::CheckedOutTwice { .. } | Problem::MissingBaseBranch { .. } => false,
+        }
+    }
+    
+    /// What is wrong
+    pub fn describe(&self) -> String {
+        match self {
+            Problem::Prunable { path, reason, .. } => format!("{} is stale: {}", path.display(), reason),
+            Problem::BrokenGitFile { path } => {
+                format!("{} has a .git file that does not point back to the repository", path.display())
+            }
+            Problem::Moved { from, to } => format!("{} was moved to {}", from.display(), to.display()),
+            Problem::Orphan { path } => format!("{} is not a registered worktree", path.display()),
+            Problem::CheckedOutTwice { branch, paths } => {
+                let paths: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+                format!("branch '{}' is checked out in {}", branch, paths.join(" and "))
+            }
+            Problem::MissingBaseBranch { branch } => format!("base branch '{}' does not exist", branch),
+        }
+    }
+    
+    /// What to do about a problem `gwtr repair` leaves alone
+    fn advice(&self) -> &'static str {
+        match self {
+            Problem::Prunable { .. } => "unlock it with `gwtr unlock` or `git worktree unlock`, then run gwtr repair",
+            Problem::Orphan { .. } => "delete the directory or register it with `git worktree add`",
+            Problem::CheckedOutTwice { .. } => "switch all but one of the worktrees to another branch",
+            Problem::MissingBaseBranch { .. } => "create the branch or set `base_branch` in .gwtr.toml",
+            Problem::BrokenGitFile { .. } | Problem::Moved { .. } => "",
+        }
+    }
+}
+
+/// The directory a worktree's `.git` file points to, if it is a linked worktree
+fn gitdir_of(path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(path.join(".git")).ok()?;
+    let gitdir = content.trim().strip_prefix("gitdir: ")?;
+    Some(path.join(gitdir))
+}
+
+/// The entries under `<common>/worktrees` and the directory each one was registered at
+fn registered_paths(common_dir: &Path) -> BTreeMap<PathBuf, PathBuf> {
+    let mut registered = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir(common_dir.join("worktrees")) else {
+        return registered;
+    };
+    
+    for entry in entries.flatten() {
+        let admin_dir = entry.path();
+        if let Ok(gitdir) = std::fs::read_to_string(admin_dir.join("gitdir")) {
+            let path = PathBuf::from(gitdir.trim());
+            let path = path.parent().map(Path::to_path_buf).unwrap_or(path);
+            registered.insert(canonical(&admin_dir), path);
+        }
+    }
+    
+    registered
+}
+
+/// Directories matching the layout template
+fn layout_directories(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let Some((parent, prefix, suffix)) = layout_pattern(repo)? else {
+        return Ok(Vec::new());
+    };
+    let Ok(entries) = std::fs::read_dir(&parent) else {
+        return Ok(Vec::new());
+    };
+    let common_dir = canonical(&get_common_dir(repo));
+    
+    let mut directories: Vec<PathBuf> = entries
+        .flatten()
+        // The git directory of a bare layout lives next to its worktrees
+        .filter(|entry| entry.path().is_dir() && canonical(&entry.path()) != common_dir)
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                name.len() > prefix.len() + suffix.len()
+                    && name.starts_with(prefix.as_str())
+                    && name.ends_with(suffix.as_str())
+            })
+        })
+        .map(|entry| entry.path())
+        .collect();
+    directories.sort();
+    
+    Ok(directories)
+}
+
+/// Find broken or stale worktree state
+pub fn diagnose(repo: &Repository) -> Result<Vec<Problem>> {
+    let config = Config::load(repo)?;
+    let common_dir = get_common_dir(repo);
+    let worktrees = get_worktrees(repo)?;
+    let registered = registered_paths(&common_dir);
+    let mut problems = Vec::new();
+    
+    // Directories in the layout that git does not know about; those whose .git
+    // file still points to one of our entries were moved there by hand
+    let known: Vec<PathBuf> = worktrees.iter().map(|worktree| canonical(&worktree.path)).collect();
+    let mut moved = BTreeMap::new();
+    for directory in layout_directories(repo)? {
+        if known.contains(&canonical(&directory)) {
+            continue;
+        }
+        match gitdir_of(&directory).and_then(|gitdir| registered.get(&canonical(&gitdir))) {
+            Some(from) if !from.exists() => {
+                moved.insert(from.clone(), directory.clone());
+                problems.push(Problem::Moved { from: from.clone(), to: directory });
+            }
+            _ => problems.push(Problem::Orphan { path: directory }),
+        }
+    }
+    
+    let mut checked_out: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for worktree in &worktrees {
+        if worktree.is_bare {
+            continue;
+        }
+        
+        if let Some(reason) = &worktree.prunable {
+            if !moved.contains_key(&worktree.path) {
+                problems.push(Problem::Prunable {
+                    path: worktree.path.clone(),
+                    reason: reason.clone(),
+                    locked: worktree.locked.is_some(),
+                });
+            }
+            continue;
+        }
+        
+        // Linked worktrees must point back to an entry in the common directory
+        if worktree.path.join(".git").is_file() {
+            let points_back = gitdir_of(&worktree.path)
+                .is_some_and(|gitdir| registered.contains_key(&canonical(&gitdir)));
+            if !points_back {
+                problems.push(Problem::BrokenGitFile { path: worktree.path.clone() });
+            }
+        }
+        
+        if let Some(branch) = &worktree.branch {
+            checked_out.entry(branch.clone()).or_default().push(worktree.path.clone());
+        }
+    }
+    
+    for (branch, paths) in checked_out {
+        if paths.len() > 1 {
+            problems.push(Problem::CheckedOutTwice { branch, paths });
+        }
+    }
+    
+    let base_ref = format!("refs/heads/{}", config.base_branch);
+    if repo.find_reference(&base_ref).is_err() {
+        problems.push(Problem::MissingBaseBranch { branch: config.base_branch.clone() });
+    }
+    
+    Ok(problems)
+}
+
+fn print_problems(problems: &[Problem]) {
+    for problem in problems {
+        let marker = if problem.is_repairable() {
+            "repairable".green()
+        } else {
+            "manual".yellow()
+        };
+        println!("  {} [{}]", problem.describe(), marker);
+    }
+}
+
+/// Report broken or stale worktree state
+pub fn run_doctor(repo: &Repository) -> Result<()> {
+    let problems = diagnose(repo)?;
+    
+    if problems.is_empty() {
+        println!("{}", "No problems found".green());
+        return Ok(());
+    }
+    
+    println!("{}", format!("Found {} problem(s):", problems.len()).bold());
+    print_problems(&problems);
+    
+    if problems.iter().any(Problem::is_repairable) {
+        println!();
+        println!("Run {} to fix the repairable problems", "gwtr repair".cyan());
+    }
+    
+    Ok(())
+}
+
+/// Fix what `gwtr doctor` finds with `git worktree repair` and `git worktree prune`,
+/// and print the problems that need manual attention
+pub fn repair_worktrees(repo: &Repository) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    let problems = diagnose(repo)?;
+    
+    if problems.is_empty() {
+        println!("{}", "No problems found".green());
+        return Ok(());
+    }
+    
+    let (repairable, manual): (Vec<Problem>, Vec<Problem>) =
+        problems.into_iter().partition(Problem::is_repairable);
+    
+    if !repairable.is_empty() {
+        // Repair links first so moved worktrees are not pruned as missing
+        let mut paths: Vec<String> = Vec::new();
+        for problem in &repairable {
+            match problem {
+                Problem::Moved { to, .. } => paths.push(to.to_string_lossy().to_string()),
+                Problem::BrokenGitFile { path } => paths.push(path.to_string_lossy().to_string()),
+                _ => {}
+            }
+        }
+        let mut args = vec!["worktree", "repair"];
+        args.extend(paths.iter().map(String::as_str));
+        git(workdir, &args)?;
+        
+        if repairable.iter().any(|problem| matches!(problem, Problem::Prunable { .. })) {
+            git(workdir, &["worktree", "prune"])?;
+        }
+        
+        // Keep the stored paths in line with what git now knows
+        let updated = Metadata::update(repo, |metadata| {
+            for problem in &repairable {
+                match problem {
+                    Problem::Moved { from, to } => {
+                        for entry in metadata.worktrees.values_mut().filter(|entry| &entry.path == from) {
+                            entry.path = to.clone();
+                        }
+                    }
+                    Problem::Prunable { path, .. } => metadata.worktrees.retain(|_, entry| &entry.path != path),
+                    _ => {}
+                }
+            }
+        });
+        if let Err(e) = updated {
+            eprintln!("{}: failed to update worktree metadata: {}", "Warning".yellow(), e);
+        }
+        
+        println!("{}", format!("Repaired {} problem(s):", repairable.len()).bold());
+        for problem in &repairable {
+            println!("  {}", problem.describe());
+        }
+    }
+    
+    if !manual.is_empty() {
+        if !repairable.is_empty() {
+            println!();
+        }
+        println!("{}", format!("{} problem(s) need manual attention:", manual.len()).bold());
+        for problem in &manual {
+            println!("  {}", problem.describe());
+            println!("    {}", problem.advice());
+        }
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use colored::*;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::dirty::SKIP_WORKTREE;
+use crate::{Config, confirm, get_worktrees, is_main_worktree, worktree_name};
+
+/// Column `gwtr du` sorts by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DuSort {
+    /// Worktree name
+    Name,
+    /// Size of tracked files
+    Tracked,
+    /// Size of ignored files, including artifacts
+    Ignored,
+    /// Combined size
+    #[default]
+    Total,
+}
+
+/// Disk usage of one worktree
+#[derive(Debug, Clone)]
+pub struct DiskUsage {
+    pub name: String,
+    pub path: PathBuf,
+    /// Size of the files in the index that are checked out
+    pub tracked: u64,
+    /// Size of ignored files and directories, artifacts included
+    pub ignored: u64,
+    /// Ignored directories whose name matches an artifact pattern, with their sizes
+    pub artifacts: Vec<(PathBuf, u64)>,
+}
+
+impl DiskUsage {
+    pub fn artifact_size(&self) -> u64 {
+        self.artifacts.iter().map(|(_, size)| size).sum()
+    }
+    
+    pub fn total(&self) -> u64 {
+        self.tracked + self.ignored
+    }
+}
+
+/// Format a byte count in the largest binary unit, e.g. `1.5 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Size of a file, or of everything below a directory, without following symlinks
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| path_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Measure the worktree at `path`
+fn measure(name: String, path: &Path, artifact_patterns: &[String]) -> Result<DiskUsage> {
+    let repo = Repository::open(path)
+        .with_context(|| format!("Failed to open worktree at {:?}", path))?;
+    
+    let index = repo.index()?;
+    let tracked = index.iter()
+        .filter(|entry| entry.flags_extended & SKIP_WORKTREE == 0)
+        .map(|entry| u64::from(entry.file_size))
+        .sum();
+    
+    // Ignored directories are reported as a whole rather than file by file
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_untracked(false)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false);
+    let statuses = repo.statuses(Some(&mut options))?;
+    
+    let mut ignored = 0;
+    let mut artifacts = Vec::new();
+    for entry in statuses.iter().filter(|entry| entry.status().is_ignored()) {
+        let Some(relative) = entry.path() else {
+            continue;
+        };
+        let entry_path = path.join(relative.trim_end_matches('/'));
+        let size = path_size(&entry_path);
+        ignored += size;
+        
+        let is_artifact = relative.ends_with('/')
+            && entry_path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| artifact_patterns.iter().any(|pattern| pattern == n));
+        if is_artifact {
+            artifacts.push((entry_path, size));
+        }
+    }
+    
+    Ok(DiskUsage { name, path: path.to_path_buf(), tracked, ignored, artifacts })
+}
+
+/// Measure the worktrees with the given names, or all worktrees if `names` is empty
+pub fn collect_disk_usage(repo: &Repository, names: &[String]) -> Result<Vec<DiskUsage>> {
+    let config = Config::load(repo)?;
+    let mut usages = Vec::new();
+    
+    for worktree in get_worktrees(repo)? {
+        if worktree.is_bare || worktree.prunable.is_some() {
+            continue;
+        }
+        
+        let name = if is_main_worktree(repo, &worktree.path) {
+            "main".to_string()
+        } else {
+            worktree_name(repo, &worktree.path)?
+        };
+        if !names.is_empty() && !names.contains(&name) {
+            continue;
+        }
+        
+        usages.push(measure(name, &worktree.path, &config.artifact_patterns)?);
+    }
+    
+    Ok(usages)
+}
+
+/// Show how much disk space each worktree uses, and with `clean_artifacts`
+/// delete the ignored directories matching the configured artifact patterns
+pub fn show_disk_usage(repo: &Repository, names: &[String], sort: DuSort, clean_artifacts: bool, force: bool) -> Result<()> {
+    let mut usages = collect_disk_usage(repo, names)?;
+    
+    if usages.is_empty() {
+        println!("No worktrees found");
+        return Ok(());
+    }
+    
+    match sort {
+        DuSort::Name => usages.sort_by(|a, b| a.name.cmp(&b.name)),
+        DuSort::Tracked => usages.sort_by_key(|usage| std::cmp::Reverse(usage.tracked)),
+        DuSort::Ignored => usages.sort_by_key(|usage| std::cmp::Reverse(usage.ignored)),
+        DuSort::Total => usages.sort_by_key(|usage| std::cmp::Reverse(usage.total())),
+    }
+    
+    let width = usages.iter().map(|usage| usage.name.len()).max().unwrap_or(0).max("Worktree".len());
+    println!("{}", format!("{:<width$}  {:>10}  {:>10}  {:>10}  {:>10}", "Worktree", "Tracked", "Ignored", "Artifacts", "Total").bold());
+    for usage in &usages {
+        println!("{:<width$}  {:>10}  {:>10}  {:>10}  {:>10}",
+                 usage.name.yellow(), format_size(usage.tracked), format_size(usage.ignored),
+                 format_size(usage.artifact_size()), format_size(usage.total()));
+    }
+    
+    let sum = |f: fn(&DiskUsage) -> u64| usages.iter().map(f).sum::<u64>();
+    println!("{}", format!("{:<width$}  {:>10}  {:>10}  {:>10}  {:>10}", "Total",
+                           format_size(sum(|u| u.tracked)), format_size(sum(|u| u.ignored)),
+                           format_size(sum(DiskUsage::artifact_size)), format_size(sum(DiskUsage::total))).bold());
+    
+    if !clean_artifacts {
+        return Ok(());
+    }
+    
+    let artifacts: Vec<&(PathBuf, u64)> = usages.iter().flat_map(|usage| &usage.artifacts).collect();
+    if artifacts.is_empty() {
+        println!("\nNo artifact directories to clean");
+        return Ok(());
+    }
+    
+    let total: u64 = artifacts.iter().map(|(_, size)| size).sum();
+    println!("\n{}", format!("Artifact directories ({}):", format_size(total)).bold());
+    for (path, size) in &artifacts {
+        println!("  {} ({})", path.display(), format_size(*size));
+    }
+    
+    if !force && !confirm("\nDelete these directories?")? {
+        println!("Aborted");
+        return Ok(());
+    }
+    
+    let mut freed = 0;
+    for (path, size) in artifacts {
+        match std::fs::remove_dir_all(path) {
+            Ok(()) => freed += size,
+            Err(e) => eprintln!("{}: failed to delete {:?}: {}", "Warning".yellow(), path, e),
+        }
+    }
+    println!("Freed {}", format_size(freed));
+    
+    Ok(())
+}
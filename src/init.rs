@@ -0,0 +1,150 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::path::PathBuf;
+
+use crate::config::remote_default_branch;
+use crate::rename::move_worktree;
+use crate::{
+    CONFIG_FILE_NAME, Config, Layout, canonical, config_path, dirty_report, get_common_dir, get_worktree_path,
+    get_worktrees, is_bare_layout, is_main_worktree, worktree_name,
+};
+
+/// A linked worktree that `init --migrate` moves to its template path
+struct Migration {
+    name: String,
+    from: PathBuf,
+    to: PathBuf,
+}
+
+/// The remote to record: `origin` if it exists, otherwise the only remote
+fn detect_remote(repo: &Repository) -> String {
+    let remotes = repo.remotes().ok();
+    let names: Vec<&str> = remotes.iter().flat_map(|remotes| remotes.iter().flatten()).collect();
+    match names.as_slice() {
+        [name] => name.to_string(),
+        _ => "origin".to_string(),
+    }
+}
+
+/// The base branch to record: the remote's default branch, else the branch the
+/// repository's own `HEAD` (the main worktree's, or the bare repository's) is on
+fn detect_base_branch(repo: &Repository, remote: &str) -> String {
+    remote_default_branch(repo, remote)
+        .or_else(|| {
+            let head = std::fs::read_to_string(get_common_dir(repo).join("HEAD")).ok()?;
+            head.trim().strip_prefix("ref: refs/heads/").map(str::to_string)
+        })
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Work out where each linked worktree belongs, refusing if any of them cannot be moved
+fn plan_migrations(repo: &Repository) -> Result<Vec<Migration>> {
+    let mut migrations = Vec::new();
+    let mut problems = Vec::new();
+    
+    for worktree in get_worktrees(repo)? {
+        if worktree.is_bare || is_main_worktree(repo, &worktree.path) {
+            continue;
+        }
+        
+        let name = worktree_name(repo, &worktree.path)?;
+        let target = get_worktree_path(repo, &name)?;
+        if canonical(&target) == canonical(&worktree.path) {
+            continue;
+        }
+        
+        if worktree.locked.is_some() {
+            problems.push(format!("{} is locked", worktree.path.display()));
+        } else if worktree.prunable.is_some() {
+            problems.push(format!("{} is missing (run gwtr repair)", worktree.path.display()));
+        } else if dirty_report(&worktree.path)?.is_dirty() {
+            problems.push(format!("{} has uncommitted changes", worktree.path.display()));
+        } else if target.exists() {
+            problems.push(format!("{} cannot move to {}: it already exists", worktree.path.display(), target.display()));
+        }
+        
+        migrations.push(Migration { name, from: worktree.path, to: target });
+    }
+    
+    if !problems.is_empty() {
+        let mut message = String::from("Cannot migrate worktrees:");
+        for problem in &problems {
+            message.push_str(&format!("\n  {}", problem));
+        }
+        message.push_str("\nCommit or stash changes and unlock worktrees, then run gwtr init --migrate again");
+        bail!(message);
+    }
+    
+    Ok(migrations)
+}
+
+/// Move already migrated worktrees back, newest first, reporting any that fail
+fn roll_back(repo: &Repository, migrated: &[Migration]) {
+    for migration in migrated.iter().rev() {
+        if let Err(e) = move_worktree(repo, &migration.name, &migration.to, &migration.name, &migration.from, None) {
+            eprintln!("{}: rollback failed, fix manually: {:#}", "Warning".yellow(), e);
+        }
+    }
+}
+
+/// Write `.gwtr.toml` with the detected base branch, remote and layout, and
+/// with `migrate` move linked worktrees to the locations the layout template gives
+pub fn init_repository(repo: &Repository, migrate: bool) -> Result<()> {
+    let path = config_path(repo);
+    if path.exists() {
+        bail!("{:?} already exists", path);
+    }
+    
+    let bare_layout = is_bare_layout(repo);
+    let layout = if bare_layout { Layout::Bare } else { Layout::Main };
+    let remote = detect_remote(repo);
+    let base_branch = detect_base_branch(repo, &remote);
+    
+    // Check every worktree before moving any of them
+    let migrations = if migrate { plan_migrations(repo)? } else { Vec::new() };
+    
+    for (index, migration) in migrations.iter().enumerate() {
+        if let Err(e) = move_worktree(repo, &migration.name, &migration.from, &migration.name, &migration.to, None) {
+            roll_back(repo, &migrations[..index]);
+            return Err(e.context(format!("Failed to migrate {:?}; the worktrees already moved were moved back", migration.from)));
+        }
+    }
+    
+    let config = Config {
+        base_branch,
+        remote,
+        layout: Some(layout),
+        ..Config::default()
+    };
+    let result = toml::to_string(&config)
+        .context("Failed to serialize configuration")
+        .and_then(|content| {
+            std::fs::write(&path, format!("# Written by gwtr init\n{}", content))
+                .with_context(|| format!("Failed to write {:?}", path))
+        });
+    if let Err(e) = result {
+        roll_back(repo, &migrations);
+        return Err(e);
+    }
+    
+    let layout_name = match layout {
+        Layout::Main => "main",
+        Layout::Bare => "bare",
+    };
+    println!("Wrote {} to {:?}", CONFIG_FILE_NAME, path);
+    println!("  base_branch = {}", config.base_branch.cyan());
+    println!("  remote = {}", config.remote.cyan());
+    println!("  layout = {}", layout_name.cyan());
+    
+    if !migrations.is_empty() {
+        println!("{}", format!("Moved {} worktree(s):", migrations.len()).bold());
+        for migration in &migrations {
+            println!("  {} -> {}", migration.from.display(), migration.to.display().to_string().yellow());
+        }
+    } else if migrate {
+        println!("All worktrees are already in place");
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,418 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::rename::move_worktree;
+use crate::sparse::check_out_sparse;
+use crate::{
+    branch_exists, branch_tip, forget_worktree, git, kill_tmux_session, register_worktree_metadata, remove_worktree_directory,
+};
+use crate::{
+    Metadata, WorktreeMetadata, allocate_ports, dirty_report, format_duration, get_command_dir, get_common_dir, get_trash_entries,
+    restore_from_trash,
+};
+
+/// Kind of operation recorded in the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Add,
+    Remove,
+    Prune,
+    Pull,
+    Rename,
+    Undo,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Add => "add",
+            Operation::Remove => "remove",
+            Operation::Prune => "prune",
+            Operation::Pull => "pull",
+            Operation::Rename => "rename",
+            Operation::Undo => "undo",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One operation recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Sequence number, starting at 1
+    #[serde(default)]
+    pub id: u64,
+    /// Unix timestamp of when the operation finished
+    #[serde(default)]
+    pub timestamp: u64,
+    pub operation: Operation,
+    /// Worktree name
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Commit the branch pointed to before the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Commit the branch pointed to after the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Trash entry holding uncommitted work that was deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash: Option<String>,
+    /// Whether the operation created the branch
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub branch_created: bool,
+    /// Whether the operation deleted the branch
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub branch_deleted: bool,
+    /// For rename entries, the previous worktree name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+    /// For rename entries, the previous worktree path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_path: Option<PathBuf>,
+    /// For rename entries that renamed the branch, the previous branch name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_branch: Option<String>,
+    /// For undo entries, the id of the entry that was reversed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<u64>,
+    /// For remove and prune entries, the metadata of the removed worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<WorktreeMetadata>,
+}
+
+impl JournalEntry {
+    pub fn new(operation: Operation, name: &str) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            timestamp: 0,
+            operation,
+            name: name.to_string(),
+            path: None,
+            branch: None,
+            before: None,
+            after: None,
+            trash: None,
+            branch_created: false,
+            branch_deleted: false,
+            renamed_from: None,
+            previous_path: None,
+            previous_branch: None,
+            undoes: None,
+            metadata: None,
+        }
+    }
+    
+    fn describe(&self) -> String {
+        let short = |commit: &Option<String>| {
+            commit.as_deref().map(|c| c[..c.len().min(7)].to_string()).unwrap_or_else(|| "-".to_string())
+        };
+        
+        match self.operation {
+            Operation::Undo => format!("undo #{}", self.undoes.unwrap_or(0)),
+            Operation::Pull => format!("{} -> {}", short(&self.before), short(&self.after)),
+            Operation::Add => format!("at {}", short(&self.after)),
+            Operation::Rename => {
+                let mut description = format!("from {}", self.renamed_from.as_deref().unwrap_or("?"));
+                if let Some(branch) = &self.previous_branch {
+                    description.push_str(&format!(", branch was {}", branch));
+                }
+                description
+            }
+            Operation::Remove | Operation::Prune => {
+                let mut description = format!("was at {}", short(&self.before));
+                if self.branch_deleted {
+                    description.push_str(", branch deleted");
+                }
+                if let Some(trash) = &self.trash {
+                    description.push_str(&format!(", changes in trash {}", trash));
+                }
+                description
+            }
+        }
+    }
+}
+
+/// Location of the journal, shared by all worktrees of the repository
+pub fn journal_path(repo: &Repository) -> PathBuf {
+    get_common_dir(repo).join("gwtr").join("journal.jsonl")
+}
+
+/// Read every journal entry, oldest first
+pub fn read_journal(repo: &Repository) -> Result<Vec<JournalEntry>> {
+    let path = journal_path(repo);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {:?}", path))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).with_context(|| format!("Corrupt journal entry in {:?}", path)))
+        .collect()
+}
+
+/// Append an entry to the journal, assigning its id and timestamp
+pub fn append_journal(repo: &Repository, mut entry: JournalEntry) -> Result<JournalEntry> {
+    let path = journal_path(repo);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    
+    entry.id = read_journal(repo)?.last().map(|last| last.id + 1).unwrap_or(1);
+    entry.timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    
+    Ok(entry)
+}
+
+/// Record an operation, warning instead of failing since the operation already happened
+///
+/// Returns the recorded entry; its id stays 0 if it could not be recorded.
+pub fn record_operation(repo: &Repository, entry: JournalEntry) -> JournalEntry {
+    match append_journal(repo, entry.clone()) {
+        Ok(recorded) => recorded,
+        Err(e) => {
+            eprintln!("{}: failed to record operation in journal: {}", "Warning".yellow(), e);
+            entry
+        }
+    }
+}
+
+/// Commit currently checked out in the worktree at `path`
+pub fn head_commit(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(path)
+        .output()
+        .ok()?;
+    
+    output.status.success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn undone_ids(entries: &[JournalEntry]) -> HashSet<u64> {
+    entries.iter().filter_map(|entry| entry.undoes).collect()
+}
+
+/// Show the journal, newest first
+pub fn show_journal(repo: &Repository, limit: Option<usize>) -> Result<()> {
+    let entries = read_journal(repo)?;
+    if entries.is_empty() {
+        println!("No operations recorded");
+        return Ok(());
+    }
+    
+    let undone = undone_ids(&entries);
+    let now = SystemTime::now();
+    
+    for entry in entries.iter().rev().take(limit.unwrap_or(usize::MAX)) {
+        let recorded_at = SystemTime::UNIX_EPOCH + Duration::from_secs(entry.timestamp);
+        let age = now.duration_since(recorded_at).unwrap_or_default();
+        
+        let mut line = format!("#{:<4} {:>4} ago  {:<7} {}",
+                               entry.id,
+                               format_duration(age),
+                               entry.operation.to_string().bold(),
+                               entry.name.yellow());
+        if let Some(branch) = &entry.branch {
+            line.push_str(&format!(" [{}]", branch.cyan()));
+        }
+        line.push_str(&format!(" {}", entry.describe()));
+        if undone.contains(&entry.id) {
+            line.push_str(&format!(" {}", "(undone)".dimmed()));
+        }
+        println!("{}", line);
+    }
+    
+    Ok(())
+}
+
+fn undo_add(repo: &Repository, workdir: &Path, entry: &JournalEntry) -> Result<()> {
+    let path = entry.path.as_deref().context("Journal entry has no worktree path")?;
+    
+    if path.exists() {
+        if dirty_report(path)?.is_dirty() {
+            bail!("Worktree '{}' has uncommitted changes; commit or remove them first", entry.name);
+        }
+        remove_worktree_directory(workdir, path, false)?;
+        println!("Removed worktree '{}' at {:?}", entry.name, path);
+    }
+    forget_worktree(repo, &entry.name);
+    kill_tmux_session(repo, &entry.name);
+    
+    // Only delete a branch the add created, and only if nothing was committed to it since
+    if let Some(branch) = entry.branch.as_deref().filter(|_| entry.branch_created) {
+        let tip = branch_tip(workdir, branch);
+        if tip.is_some() && tip == entry.after {
+            git(workdir, &["branch", "-D", branch])?;
+            println!("Deleted branch '{}'", branch.cyan());
+        } else if tip.is_some() {
+            println!("Kept branch '{}' because it has new commits", branch.cyan());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Recreate a removed or pruned worktree along with the metadata it had, so its
+/// ports, sparse checkout and temporary or detached state come back too
+fn undo_remove(repo: &Repository, workdir: &Path, entry: &JournalEntry) -> Result<()> {
+    let path = entry.path.as_deref().context("Journal entry has no worktree path")?;
+    let sparse = entry.metadata.as_ref().and_then(|metadata| metadata.sparse.as_ref());
+    
+    // Uncommitted work saved in the trash is restored together with the worktree
+    if let Some(trash) = &entry.trash
+        && get_trash_entries(workdir)?.iter().any(|saved| &saved.name == trash) {
+        restore_from_trash(repo, trash)?;
+        if let Some(sparse) = sparse {
+            check_out_sparse(path, &sparse.directories)?;
+        }
+    } else {
+        if path.exists() {
+            bail!("Cannot recreate worktree '{}': {:?} already exists", entry.name, path);
+        }
+        let path_arg = path.to_string_lossy();
+        
+        // A sparse worktree is checked out only after its directories are set
+        let mut args = vec!["worktree", "add"];
+        if sparse.is_some() {
+            args.push("--no-checkout");
+        }
+        match entry.branch.as_deref() {
+            Some(branch) => {
+                if branch_exists(workdir, branch) {
+                    args.extend([&*path_arg, branch]);
+                } else {
+                    let before = entry.before.as_deref().context("Journal entry has no branch commit")?;
+                    args.extend(["-b", branch, &path_arg, before]);
+                }
+            }
+            None => {
+                let before = entry.before.as_deref().context("Journal entry has no commit")?;
+                args.extend(["--detach", &path_arg, before]);
+            }
+        }
+        git(workdir, &args)?;
+        if let Some(sparse) = sparse {
+            check_out_sparse(path, &sparse.directories)?;
+        }
+        
+        println!("Recreated worktree '{}' at {:?}", entry.name, path);
+    }
+    
+    let mut metadata = WorktreeMetadata {
+        path: path.to_path_buf(),
+        branch: entry.branch.clone(),
+        ..entry.metadata.clone().unwrap_or_default()
+    };
+    
+    // The port block may have been handed to another worktree in the meantime
+    let mut taken = Metadata::load(repo)?.worktrees.into_values().filter_map(|other| other.ports);
+    let reallocate = metadata.ports.is_some_and(|ports| taken.any(|other| other.overlaps(&ports)));
+    if reallocate {
+        metadata.ports = None;
+    }
+    register_worktree_metadata(repo, &entry.name, metadata);
+    if reallocate {
+        let ports = allocate_ports(repo, &entry.name)?;
+        println!("Ports {}-{} (its previous ports are in use)", ports.start, ports.end());
+    }
+    
+    Ok(())
+}
+
+fn undo_pull(entry: &JournalEntry) -> Result<()> {
+    let path = entry.path.as_deref().context("Journal entry has no worktree path")?;
+    let before = entry.before.as_deref().context("Journal entry has no pre-pull commit")?;
+    
+    if head_commit(path) != entry.after {
+        bail!("Worktree '{}' has moved on since the pull; refusing to reset it", entry.name);
+    }
+    
+    git(path, &["reset", "--keep", before])?;
+    println!("Reset '{}' to {}", entry.name, &before[..before.len().min(7)]);
+    
+    Ok(())
+}
+
+fn undo_rename(repo: &Repository, entry: &JournalEntry) -> Result<()> {
+    let path = entry.path.as_deref().context("Journal entry has no worktree path")?;
+    let previous_name = entry.renamed_from.as_deref().context("Journal entry has no previous name")?;
+    let previous_path = entry.previous_path.as_deref().context("Journal entry has no previous path")?;
+    
+    if previous_path.exists() {
+        bail!("Cannot rename back to '{}': {:?} already exists", previous_name, previous_path);
+    }
+    
+    let branch_rename = entry.branch.as_deref().zip(entry.previous_branch.as_deref());
+    move_worktree(repo, &entry.name, path, previous_name, previous_path, branch_rename)?;
+    println!("Renamed worktree '{}' back to '{}' at {:?}", entry.name, previous_name, previous_path);
+    
+    Ok(())
+}
+
+/// Reverse the most recent operation that has not been undone yet
+pub fn undo_last_operation(repo: &Repository) -> Result<()> {
+    let entries = read_journal(repo)?;
+    let undone = undone_ids(&entries);
+    
+    let Some(entry) = entries
+        .iter()
+        .rev()
+        .find(|entry| entry.operation != Operation::Undo && !undone.contains(&entry.id))
+    else {
+        println!("Nothing to undo");
+        return Ok(());
+    };
+    
+    println!("Undoing #{} {} {}", entry.id, entry.operation, entry.name.yellow());
+    undo_operation(repo, entry)
+}
+
+/// Reverse one operation, and record the undo if the operation is in the journal
+pub(crate) fn undo_operation(repo: &Repository, entry: &JournalEntry) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    
+    match entry.operation {
+        Operation::Add => undo_add(repo, workdir, entry)?,
+        Operation::Remove | Operation::Prune => undo_remove(repo, workdir, entry)?,
+        Operation::Pull => undo_pull(entry)?,
+        Operation::Rename => undo_rename(repo, entry)?,
+        Operation::Undo => bail!("An undo cannot be undone"),
+    }
+    
+    if entry.id != 0 {
+        let mut undo = JournalEntry::new(Operation::Undo, &entry.name);
+        undo.undoes = Some(entry.id);
+        record_operation(repo, undo);
+    }
+    
+    Ok(())
+}
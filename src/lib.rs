@@ -1,20 +1,133 @@
 use anyhow::{Context, Result, bail};
 use git2::Repository;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use colored::*;
 
+mod adopt;
+mod branch;
+mod clone;
+mod config;
+mod dirty;
+mod doctor;
+mod du;
+mod init;
+mod journal;
+mod merge;
+mod metadata;
+mod open;
+mod ports;
+mod prune;
+mod registry;
+mod rename;
+mod temporary;
+mod tmux;
+mod sparse;
+mod submodule;
+mod trash;
+mod workspace;
+
+pub use adopt::adopt_worktree;
+pub use branch::delete_branch;
+pub use clone::clone_repository;
+pub use config::{
+    CONFIG_FILE_NAME, Config, DEFAULT_BARE_PATH_TEMPLATE, DEFAULT_PATH_TEMPLATE, Layout,
+    TmuxConfig, TmuxWindow, USER_CONFIG_FILE_NAME, config_path, user_config_dir,
+};
+pub use dirty::{DirtyReport, confirm, dirty_report};
+pub use doctor::{Problem, diagnose, repair_worktrees, run_doctor};
+pub use du::{DiskUsage, DuSort, collect_disk_usage, format_size, show_disk_usage};
+pub use init::init_repository;
+pub use journal::{
+    JournalEntry, Operation, append_journal, head_commit, journal_path, read_journal,
+    record_operation, show_journal, undo_last_operation,
+};
+pub use merge::{MergeRule, detect_merge};
+pub use metadata::{Metadata, PortBlock, WorktreeMetadata, metadata_path};
+pub use open::open_worktree;
+pub use ports::{allocate_ports, show_env, worktree_env, write_env_file};
+pub use prune::{PruneOptions, PruneReason, prune_merged_worktrees};
+pub use registry::{
+    Registry, for_each_repository, register_repository, registered_repositories, registry_path,
+};
+pub use rename::rename_worktree;
+pub use sparse::{SparseAction, SparseCheckout, sparse_profile, sparse_worktree};
+pub use submodule::{SubmoduleReport, submodule_report, update_submodules};
+pub use temporary::{Temporary, create_temporary_worktree, expire_temporaries};
+pub use tmux::{kill_tmux_session, tmux_session, tmux_session_name};
+pub use trash::{
+    TRASH_REF_PREFIX, TrashEntry, drop_trash, get_trash_entries, list_trash, restore_from_trash,
+    save_to_trash,
+};
+pub use workspace::{
+    WORKSPACE_FILE_NAME, Workspace, workspace_add, workspace_prune, workspace_remove,
+    workspace_status,
+};
+
+/// Parse a duration such as `30d`, `2w`, `12h`, `45m` or `90s`
+pub fn parse_duration(value: &str) -> Result<std::time::Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    
+    let amount: u64 = amount.parse()
+        .with_context(|| format!("Invalid duration '{}': expected a number followed by s, m, h, d or w", value))?;
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => bail!("Invalid duration unit '{}' in '{}': expected s, m, h, d or w", unit, value),
+    };
+    
+    let seconds = amount.checked_mul(seconds_per_unit)
+        .with_context(|| format!("Invalid duration '{}': duration too large", value))?;
+    Ok(std::time::Duration::from_secs(seconds))
+}
+
+/// Format a duration in the largest whole unit, e.g. `3d` or `5h`
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        s if s >= 24 * 60 * 60 => format!("{}d", s / (24 * 60 * 60)),
+        s if s >= 60 * 60 => format!("{}h", s / (60 * 60)),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
 /// Check if the current directory is inside a git repository
+///
+/// The repository is also remembered in the user-level registry for the `--global` commands.
 pub fn ensure_git_repository(path: &Path) -> Result<Repository> {
-    Repository::discover(path)
-        .context("Not in a git repository. Please run this command inside a git repository.")
+    let repo = discover_repository(path)
+        .context("Not in a git repository. Please run this command inside a git repository.")?;
+    register_repository(&repo);
+    
+    Ok(repo)
+}
+
+/// `path` with symlinks and `..` resolved for comparing paths, or unchanged if it does not exist
+pub(crate) fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Find the repository containing `path`
+pub(crate) fn discover_repository(path: &Path) -> Result<Repository, git2::Error> {
+    // Unlike `Repository::discover`, this keeps the working directory of a checkout
+    // whose `.git` is a file, e.g. one made with --separate-git-dir
+    Repository::open_ext(path, git2::RepositoryOpenFlags::empty(), &[] as &[&std::ffi::OsStr])
 }
 
 /// Get the repository name from the current git repository
+///
+/// This is the name of the main worktree, or of the project directory of a bare
+/// layout such as `project/.bare`.
 pub fn get_repository_name(repo: &Repository) -> Result<String> {
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
+    let project_root = get_project_root(repo);
     
-    let repo_name = workdir
+    let repo_name = project_root
         .file_name()
         .context("Failed to get repository directory name")?
         .to_str()
@@ -23,120 +136,344 @@ pub fn get_repository_name(repo: &Repository) -> Result<String> {
     Ok(repo_name.to_string())
 }
 
+/// Get the git directory shared by all worktrees of the repository
+///
+/// For a linked worktree this is the main repository's git directory rather than
+/// the worktree's private `.git/worktrees/<id>` directory.
+pub fn get_common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    
+    std::fs::read_to_string(git_dir.join("commondir"))
+        .ok()
+        .map(|common_dir| git_dir.join(common_dir.trim()))
+        .and_then(|common_dir| common_dir.canonicalize().ok())
+        .unwrap_or_else(|| git_dir.to_path_buf())
+}
+
+/// Directory to run repository-wide git commands in
+///
+/// This is the current worktree, or the git directory itself for a bare repository.
+pub fn get_command_dir(repo: &Repository) -> &Path {
+    repo.workdir().unwrap_or_else(|| repo.path())
+}
+
+/// Whether the repository's worktrees all hang off a bare repository, as in
+/// `project/.bare` with `project/main` and `project/feature` next to it
+pub fn is_bare_layout(repo: &Repository) -> bool {
+    git2::Config::open(&get_common_dir(repo).join("config"))
+        .and_then(|config| config.get_bool("core.bare"))
+        .unwrap_or(false)
+}
+
+/// Directory the worktree layout is relative to: the main worktree, or the
+/// directory containing the git directory of a bare layout
+pub fn get_project_root(repo: &Repository) -> PathBuf {
+    let common_dir = get_common_dir(repo);
+    
+    // The main worktree is not always the parent of the git directory, e.g. for
+    // submodules or checkouts made with --separate-git-dir
+    if !is_bare_layout(repo) {
+        let main_workdir = if repo.is_worktree() {
+            Repository::open(&common_dir).ok().and_then(|main| main.workdir().map(Path::to_path_buf))
+        } else {
+            repo.workdir().map(Path::to_path_buf)
+        };
+        if let Some(main_workdir) = main_workdir {
+            return main_workdir.canonicalize().unwrap_or(main_workdir);
+        }
+    }
+    
+    common_dir
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or(common_dir)
+}
+
+/// Get the main worktree, or `None` for a bare layout where no worktree is special
+pub fn get_main_worktree(repo: &Repository) -> Option<PathBuf> {
+    (!is_bare_layout(repo)).then(|| get_project_root(repo))
+}
+
+/// Whether `path` is the main worktree
+pub fn is_main_worktree(repo: &Repository, path: &Path) -> bool {
+    get_main_worktree(repo).is_some_and(|main| canonical(&main) == canonical(path))
+}
+
+/// Get the path of the worktree with the given name
+///
+/// The path comes from the configured `path_template`, by default
+/// `../{repository_name}_{worktree_name}` next to the main worktree, or
+/// `{worktree_name}` inside the project directory of a bare layout.
+pub fn get_worktree_path(repo: &Repository, worktree_name: &str) -> Result<PathBuf> {
+    let config = Config::load(repo)?;
+    let repo_name = get_repository_name(repo)?;
+    let project_root = get_project_root(repo);
+    
+    let relative = config.path_template(is_bare_layout(repo))
+        .replace("{repo}", &repo_name)
+        .replace("{name}", worktree_name);
+    
+    Ok(normalize_path(&project_root.join(relative)))
+}
+
+/// The directory that holds worktrees and the prefix and suffix around `{name}`
+/// in their directory names, if the layout template puts `{name}` in the last
+/// path component
+pub(crate) fn layout_pattern(repo: &Repository) -> Result<Option<(PathBuf, String, String)>> {
+    const PLACEHOLDER: &str = "\u{0}name\u{0}";
+    
+    let probe = get_worktree_path(repo, PLACEHOLDER)?;
+    let (Some(parent), Some(pattern)) = (probe.parent(), probe.file_name().and_then(|n| n.to_str())) else {
+        return Ok(None);
+    };
+    if parent.to_string_lossy().contains(PLACEHOLDER) {
+        return Ok(None);
+    }
+    
+    Ok(pattern.split_once(PLACEHOLDER)
+        .map(|(prefix, suffix)| (parent.to_path_buf(), prefix.to_string(), suffix.to_string())))
+}
+
+/// Find the path of the worktree with the given name
+///
+/// Names registered by `add`, `adopt` or `rename` take precedence over the path
+/// computed from the layout template.
+pub fn resolve_worktree_path(repo: &Repository, worktree_name: &str) -> Result<PathBuf> {
+    match Metadata::load(repo)?.worktrees.get(worktree_name) {
+        Some(entry) => Ok(entry.path.clone()),
+        None => get_worktree_path(repo, worktree_name),
+    }
+}
+
+/// Get the name of the worktree at `path`
+///
+/// This is its registered name, or else the directory name without the
+/// surrounding layout template, e.g. `feature` for `../myapp_feature`.
+pub fn worktree_name(repo: &Repository, path: &Path) -> Result<String> {
+    if let Some((name, _)) = Metadata::load(repo)?.entry_for_path(path) {
+        return Ok(name.to_string());
+    }
+    
+    let directory_name = path.file_name()
+        .and_then(|n| n.to_str())
+        .context("Worktree path has no directory name")?;
+    
+    if let Some((parent, prefix, suffix)) = layout_pattern(repo)?
+        && path.parent().is_some_and(|p| canonical(p) == canonical(&parent))
+        && let Some(name) = directory_name.strip_prefix(prefix.as_str()).and_then(|n| n.strip_suffix(suffix.as_str()))
+        && !name.is_empty() {
+        return Ok(name.to_string());
+    }
+    
+    Ok(directory_name.to_string())
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+    
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Options for `create_worktree_with`
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    /// Sparse profile from the configuration to check out instead of the whole tree
+    pub sparse: Option<String>,
+    /// Check out this ref with a detached HEAD instead of a branch named after the worktree
+    pub detach: Option<String>,
+    /// Where a new branch starts, instead of the current HEAD
+    pub start_point: Option<String>,
+}
+
 /// Create a new worktree with the specified name
 pub fn create_worktree(repo: &Repository, worktree_name: &str) -> Result<PathBuf> {
-    // Get repository name and parent directory
-    let repo_name = get_repository_name(repo)?;
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    let parent_dir = workdir.parent()
-        .context("Failed to get parent directory of repository")?;
+    create_worktree_with(repo, worktree_name, &AddOptions::default())
+}
+
+/// Create a new worktree with the specified name and options
+pub fn create_worktree_with(repo: &Repository, worktree_name: &str, options: &AddOptions) -> Result<PathBuf> {
+    create_worktree_journaled(repo, worktree_name, options).map(|(path, _)| path)
+}
+
+/// Like [`create_worktree_with`], also returning the journal entry of the add
+///
+/// Nothing is left behind when this fails, so a caller that has to reverse the
+/// add only needs the entry of a successful one.
+pub(crate) fn create_worktree_journaled(repo: &Repository, worktree_name: &str, options: &AddOptions) -> Result<(PathBuf, JournalEntry)> {
+    let config = Config::load(repo)?;
+    let workdir = get_command_dir(repo);
     
     // Construct worktree path: ../repo-name_worktree-name
-    let worktree_path = parent_dir.join(format!("{}_{}", repo_name, worktree_name));
+    let worktree_path = get_worktree_path(repo, worktree_name)?;
     
     // Check if worktree already exists
     if worktree_path.exists() {
         bail!("Worktree '{}' already exists at {:?}", worktree_name, worktree_path);
     }
     
+    let sparse = match &options.sparse {
+        Some(profile) => Some(SparseCheckout {
+            profile: Some(profile.clone()),
+            directories: sparse_profile(&config, profile)?,
+        }),
+        None => None,
+    };
+    
     // Use git command to create worktree
     // This is more reliable than using libgit2's worktree API
     use std::process::Command;
     
-    let output = Command::new("git")
-        .args(&["worktree", "add", "-b", worktree_name, worktree_path.to_str().unwrap()])
-        .current_dir(workdir)
-        .output()
-        .context("Failed to execute git worktree command")?;
+    // A sparse worktree is checked out only after its directories are set
+    let no_checkout: &[&str] = if sparse.is_some() { &["--no-checkout"] } else { &[] };
     
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    let branch_created = if let Some(reference) = &options.detach {
+        let commit = format!("{}^{{commit}}", reference);
+        let exists = Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", &commit])
+            .current_dir(workdir)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        if !exists {
+            bail!("Unknown ref '{}': not a commit, branch or tag", reference);
+        }
         
-        // Check if branch already exists and retry without -b flag
-        if stderr.contains("already exists") {
-            let output = Command::new("git")
-                .args(&["worktree", "add", worktree_path.to_str().unwrap(), worktree_name])
-                .current_dir(workdir)
-                .output()
-                .context("Failed to execute git worktree command")?;
+        let output = Command::new("git")
+            .args(["worktree", "add", "--detach"])
+            .args(no_checkout)
+            .args([worktree_path.to_str().unwrap(), &commit])
+            .current_dir(workdir)
+            .output()
+            .context("Failed to execute git worktree command")?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("Failed to create worktree: {}", stderr);
+        }
+        false
+    } else {
+        let output = Command::new("git")
+            .args(["worktree", "add"])
+            .args(no_checkout)
+            .args(["-b", worktree_name, worktree_path.to_str().unwrap()])
+            .args(options.start_point.as_deref())
+            .current_dir(workdir)
+            .output()
+            .context("Failed to execute git worktree command")?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
             
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
+            // Check if branch already exists and retry without -b flag
+            if stderr.contains("already exists") {
+                let output = Command::new("git")
+                    .args(["worktree", "add"])
+                    .args(no_checkout)
+                    .args([worktree_path.to_str().unwrap(), worktree_name])
+                    .current_dir(workdir)
+                    .output()
+                    .context("Failed to execute git worktree command")?;
+                
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    bail!("Failed to create worktree: {}", stderr);
+                }
+            } else {
                 bail!("Failed to create worktree: {}", stderr);
             }
-        } else {
-            bail!("Failed to create worktree: {}", stderr);
         }
+        output.status.success()
+    };
+    let branch = options.detach.is_none().then_some(worktree_name);
+    
+    if let Some(sparse) = &sparse
+        && let Err(e) = sparse::check_out_sparse(&worktree_path, &sparse.directories) {
+        // Leave nothing half set up behind
+        let _ = Command::new("git")
+            .args(["worktree", "remove", "--force", worktree_path.to_str().unwrap()])
+            .current_dir(workdir)
+            .output();
+        if branch_created {
+            let _ = Command::new("git")
+                .args(["branch", "-D", worktree_name])
+                .current_dir(workdir)
+                .output();
+        }
+        return Err(e.context("Failed to set up the sparse checkout; the worktree was not created"));
+    }
+    
+    match &options.detach {
+        Some(reference) => println!("Created worktree '{}' at {:?} (detached at {})", worktree_name, worktree_path, reference),
+        None => println!("Created worktree '{}' at {:?}", worktree_name, worktree_path),
     }
     
-    println!("Created worktree '{}' at {:?}", worktree_name, worktree_path);
+    register_worktree(repo, worktree_name, &worktree_path, branch);
+    if let Some(reference) = &options.detach {
+        let result = Metadata::update(repo, |metadata| {
+            if let Some(entry) = metadata.worktrees.get_mut(worktree_name) {
+                entry.detached_from = Some(reference.clone());
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("{}: failed to record worktree metadata: {}", "Warning".yellow(), e);
+        }
+    }
+    if let Some(sparse) = sparse {
+        println!("Sparse checkout: {}", sparse.describe());
+        let result = Metadata::update(repo, |metadata| {
+            if let Some(entry) = metadata.worktrees.get_mut(worktree_name) {
+                entry.sparse = Some(sparse);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("{}: failed to record worktree metadata: {}", "Warning".yellow(), e);
+        }
+    }
+    if config.submodules {
+        submodule::update_submodules_or_warn(repo, &worktree_path);
+    }
+    ports::set_up_worktree_env(repo, worktree_name, &worktree_path);
     println!("cd '{}'", worktree_path.display());
     
-    Ok(worktree_path)
+    let mut entry = JournalEntry::new(Operation::Add, worktree_name);
+    entry.path = Some(worktree_path.clone());
+    entry.branch = branch.map(str::to_string);
+    entry.after = head_commit(&worktree_path);
+    entry.branch_created = branch_created;
+    let entry = record_operation(repo, entry);
+    
+    Ok((worktree_path, entry))
 }
 
 /// List all worktrees for the current repository
 pub fn list_worktrees(repo: &Repository) -> Result<()> {
-    use std::process::Command;
-    
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    
-    // Use git worktree list command
-    let output = Command::new("git")
-        .args(&["worktree", "list", "--porcelain"])
-        .current_dir(workdir)
-        .output()
-        .context("Failed to execute git worktree list command")?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to list worktrees: {}", stderr);
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
+    let worktrees = get_worktrees(repo)?;
     
-    if lines.is_empty() {
+    if worktrees.is_empty() {
         println!("No worktrees found");
         return Ok(());
     }
     
-    // Parse the porcelain output
-    let mut worktrees = Vec::new();
-    let mut i = 0;
-    while i < lines.len() {
-        if lines[i].starts_with("worktree ") {
-            let path = lines[i].strip_prefix("worktree ").unwrap_or("");
-            let mut branch = "detached";
-            let mut is_bare = false;
-            
-            // Look for additional info
-            if i + 1 < lines.len() && lines[i + 1].starts_with("HEAD ") {
-                i += 1;
-            }
-            if i + 1 < lines.len() && lines[i + 1].starts_with("branch ") {
-                branch = lines[i + 1].strip_prefix("branch refs/heads/").unwrap_or("unknown");
-                i += 1;
-            }
-            if i + 1 < lines.len() && lines[i + 1] == "bare" {
-                is_bare = true;
-                i += 1;
-            }
-            
-            worktrees.push((path.to_string(), branch.to_string(), is_bare));
-        }
-        i += 1;
-    }
+    let metadata = Metadata::load(repo)?;
     
     // Display worktrees
     println!("{}", "Worktrees:".bold());
-    let main_path = workdir.to_string_lossy().trim_end_matches('/').to_string();
     
-    for (path, branch, is_bare) in worktrees {
-        let normalized_path = path.trim_end_matches('/');
-        let is_main = normalized_path == main_path;
+    for worktree in worktrees {
+        let path = worktree.path.to_string_lossy().to_string();
+        let branch = branch_label(&metadata, &worktree);
+        let is_main = is_main_worktree(repo, &worktree.path);
         
         let display_path = if is_main {
             format!("{} (main)", path).green()
@@ -144,78 +481,391 @@ pub fn list_worktrees(repo: &Repository) -> Result<()> {
             path.yellow()
         };
         
-        if is_bare {
+        if worktree.is_bare {
             println!("  {} [bare]", display_path);
         } else {
-            println!("  {} [{}]", display_path, branch.cyan());
+            println!("  {} [{}]{}{}{}{}", display_path, branch.cyan(), lock_suffix(&worktree),
+                     prunable_suffix(&worktree), sparse_suffix(&metadata, &worktree), temporary_suffix(&metadata, &worktree));
         }
     }
     
     Ok(())
 }
 
+/// The branch of a worktree, or `detached at <commit>` with the ref it was
+/// created at while HEAD still points there
+pub fn branch_label(metadata: &Metadata, worktree: &WorktreeInfo) -> String {
+    if let Some(branch) = &worktree.branch {
+        return branch.clone();
+    }
+    
+    let head = worktree.head.as_deref().unwrap_or_default();
+    let short = &head[..head.len().min(7)];
+    
+    match detached_at_created_ref(metadata, worktree) {
+        Some(reference) => format!("detached at {} ({})", reference, short),
+        None => format!("detached at {}", short),
+    }
+}
+
+/// The ref a detached worktree was created at, if its HEAD is still there
+pub(crate) fn detached_at_created_ref<'a>(metadata: &'a Metadata, worktree: &WorktreeInfo) -> Option<&'a str> {
+    let head = worktree.head.as_deref()?;
+    
+    metadata.entry_for_path(&worktree.path)
+        .and_then(|(_, entry)| entry.detached_from.as_deref())
+        .filter(|reference| {
+            std::process::Command::new("git")
+                .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", reference)])
+                .current_dir(&worktree.path)
+                .output()
+                .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == head)
+        })
+}
+
+/// ` sparse: <profile> (<dirs>)` for worktrees gwtr made sparse, empty otherwise
+fn sparse_suffix(metadata: &Metadata, worktree: &WorktreeInfo) -> String {
+    metadata.entry_for_path(&worktree.path)
+        .and_then(|(_, entry)| entry.sparse.as_ref())
+        .map(|sparse| format!(" {}", format!("sparse: {}", sparse.describe()).blue()))
+        .unwrap_or_default()
+}
+
+/// ` temporary: expires in <duration>` for worktrees created by `gwtr tmp`, empty otherwise
+fn temporary_suffix(metadata: &Metadata, worktree: &WorktreeInfo) -> String {
+    metadata.entry_for_path(&worktree.path)
+        .and_then(|(_, entry)| entry.temporary)
+        .map(|temporary| format!(" {}", format!("temporary: {}", temporary.describe()).magenta()))
+        .unwrap_or_default()
+}
+
+/// ` prunable: <reason>` for stale entries, empty otherwise
+fn prunable_suffix(worktree: &WorktreeInfo) -> String {
+    match worktree.prunable.as_deref() {
+        None => String::new(),
+        Some("") => format!(" {} (run gwtr doctor)", "prunable".red()),
+        Some(reason) => format!(" {} (run gwtr doctor)", format!("prunable: {}", reason).red()),
+    }
+}
+
+/// ` locked: <reason>` for locked worktrees, empty otherwise
+fn lock_suffix(worktree: &WorktreeInfo) -> String {
+    match worktree.locked.as_deref() {
+        None => String::new(),
+        Some("") => format!(" {}", "locked".red()),
+        Some(reason) => format!(" {}", format!("locked: {}", reason).red()),
+    }
+}
+
+/// Options for `remove_worktree`
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+    /// Delete the branch after removing the worktree; `None` uses the configured default
+    pub delete_branch: Option<bool>,
+    /// Also delete the branch on its remote; `None` uses the configured default
+    pub delete_remote_branch: Option<bool>,
+    /// Remove the worktree even if it has uncommitted changes, and delete the
+    /// branch even if it is not merged
+    pub force: bool,
+}
+
+/// Store the metadata of a created worktree
+pub(crate) fn register_worktree(repo: &Repository, worktree_name: &str, path: &Path, branch: Option<&str>) {
+    register_worktree_metadata(repo, worktree_name, WorktreeMetadata {
+        path: path.to_path_buf(),
+        branch: branch.map(str::to_string),
+        ..Default::default()
+    });
+}
+
+/// Record the metadata of a worktree, e.g. the saved metadata of one brought back by undo
+pub(crate) fn register_worktree_metadata(repo: &Repository, worktree_name: &str, entry: WorktreeMetadata) {
+    let result = Metadata::update(repo, |metadata| {
+        metadata.worktrees.insert(worktree_name.to_string(), entry);
+    });
+    if let Err(e) = result {
+        eprintln!("{}: failed to record worktree metadata: {}", "Warning".yellow(), e);
+    }
+}
+
+/// Drop the stored metadata of a removed worktree, returning what was stored
+pub(crate) fn forget_worktree(repo: &Repository, worktree_name: &str) -> Option<WorktreeMetadata> {
+    let mut forgotten = None;
+    let result = Metadata::update(repo, |metadata| {
+        forgotten = metadata.worktrees.remove(worktree_name);
+    });
+    if let Err(e) = result {
+        eprintln!("{}: failed to update worktree metadata: {}", "Warning".yellow(), e);
+    }
+    forgotten
+}
+
+/// Which whitespace [`run_git`] trims from the output of a git command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Trim {
+    /// Trim both ends
+    Both,
+    /// Trim only the end, for output where leading whitespace is significant
+    End,
+}
+
+/// Run git in `dir` and return its trimmed output, failing with git's error message
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    run_git(dir, args, &[], Trim::Both)
+}
+
+/// Run git in `dir` with extra environment variables, trimming its output as `trim` says
+pub(crate) fn run_git(dir: &Path, args: &[&str], env: &[(&str, &OsStr)], trim: Trim) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .envs(env.iter().copied())
+        .output()
+        .with_context(|| format!("Failed to execute git {}", args.first().unwrap_or(&"")))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args.join(" "), stderr.trim());
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(match trim {
+        Trim::Both => stdout.trim(),
+        Trim::End => stdout.trim_end(),
+    }.to_string())
+}
+
+/// Delete a worktree directory with `git worktree remove`
+///
+/// `--force` is passed when `discard_changes` confirms that uncommitted work may
+/// go, and for checked-out submodules, which git refuses to remove otherwise.
+pub(crate) fn remove_worktree_directory(workdir: &Path, path: &Path, discard_changes: bool) -> Result<()> {
+    let mut args = vec!["worktree", "remove"];
+    if discard_changes || submodule::has_initialized_submodules(path) {
+        args.push("--force");
+    }
+    let path = path.to_string_lossy();
+    args.push(&path);
+    
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git worktree remove command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.trim());
+    }
+    
+    Ok(())
+}
+
+/// Whether a local branch with the given name exists
+pub(crate) fn branch_exists(dir: &Path, branch: &str) -> bool {
+    branch_tip(dir, branch).is_some()
+}
+
+/// The commit a local branch points at, if the branch exists
+pub(crate) fn branch_tip(dir: &Path, branch: &str) -> Option<String> {
+    git(dir, &["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)]).ok()
+}
+
+/// Branches, tags and remote-tracking branches that contain `commit`
+pub(crate) fn refs_containing(dir: &Path, commit: &str) -> Result<Vec<String>> {
+    let args = ["for-each-ref", "--contains", commit, "--format=%(refname)", "refs/heads", "refs/tags", "refs/remotes"];
+    let refs = git(dir, &args)
+        .with_context(|| format!("Failed to find refs containing {}", commit))?;
+    Ok(refs.lines().map(str::to_string).collect())
+}
+
+/// Find the branch checked out in the worktree at `path`
+pub(crate) fn branch_of_worktree(repo: &Repository, path: &Path) -> Result<Option<String>> {
+    let path = canonical(path);
+    
+    Ok(get_worktrees(repo)?
+        .into_iter()
+        .find(|worktree| canonical(&worktree.path) == path)
+        .and_then(|worktree| worktree.branch))
+}
+
 /// Remove a worktree
-pub fn remove_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
+pub fn remove_worktree(repo: &Repository, worktree_name: &str, options: &RemoveOptions) -> Result<()> {
+    remove_worktree_journaled(repo, worktree_name, options, &mut None)
+}
+
+/// Like [`remove_worktree`], storing the journal entry in `removed` once the
+/// worktree is gone, so a caller can reverse the removal even if a later step
+/// such as deleting the branch fails
+pub(crate) fn remove_worktree_journaled(
+    repo: &Repository,
+    worktree_name: &str,
+    options: &RemoveOptions,
+    removed: &mut Option<JournalEntry>,
+) -> Result<()> {
+    let config = Config::load(repo)?;
+    let workdir = get_command_dir(repo);
+    
+    // Find the worktree by name
+    let worktree_path = resolve_worktree_path(repo, worktree_name)?;
+    
+    // Check if worktree exists
+    if !worktree_path.exists() {
+        bail!("Worktree '{}' not found at {:?}", worktree_name, worktree_path);
+    }
+    
+    let branch = branch_of_worktree(repo, &worktree_path)?;
+    let delete = options.delete_branch.unwrap_or(config.delete_branch);
+    
+    // Refuse before touching the worktree if its branch could not be deleted afterwards
+    if delete && !options.force
+        && let Some(branch) = &branch
+        && detect_merge(workdir, branch, &config.base_branch).ok().flatten().is_none() {
+        bail!("Branch '{}' is not merged into '{}'; use --force to delete it anyway", branch, config.base_branch);
+    }
+    
+    // Never discard uncommitted work without an explicit --force or confirmation
+    let report = dirty_report(&worktree_path)?;
+    if report.is_dirty() {
+        println!("Worktree '{}' has uncommitted changes that would be lost:", worktree_name);
+        report.print();
+        
+        if !options.force && !confirm("Remove it anyway?")? {
+            bail!("Refusing to remove worktree '{}' with uncommitted changes; use --force to remove it anyway", worktree_name);
+        }
+    } else if !report.ignored.is_empty() {
+        println!("Worktree '{}' has no uncommitted changes", worktree_name);
+        report.print();
+    }
+    
+    let mut journal_entry = JournalEntry::new(Operation::Remove, worktree_name);
+    journal_entry.path = Some(worktree_path.clone());
+    journal_entry.branch = branch.clone();
+    journal_entry.before = head_commit(&worktree_path);
+    
+    // Keep a recoverable copy of the work that is about to be deleted
+    if report.is_dirty() {
+        let entry = match save_to_trash(&worktree_path, worktree_name, branch.as_deref()) {
+            Ok(entry) => entry,
+            Err(e) => bail!("Failed to save uncommitted changes; the worktree was not removed: {}", e),
+        };
+        println!("Saved uncommitted changes to {}{} (restore with `gwtr restore {}`)",
+                 TRASH_REF_PREFIX, entry.name, entry.name);
+        journal_entry.trash = Some(entry.name);
+    }
+    
+    if let Err(e) = remove_worktree_directory(workdir, &worktree_path, report.is_dirty()) {
+        bail!("Failed to remove worktree: {}", e);
+    }
+    
+    println!("Removed worktree '{}' at {:?}", worktree_name, worktree_path);
+    journal_entry.metadata = forget_worktree(repo, worktree_name);
+    kill_tmux_session(repo, worktree_name);
+    
+    let mut result = Ok(());
+    if delete {
+        match &branch {
+            Some(branch) => {
+                let delete_remote = options.delete_remote_branch.unwrap_or(config.delete_remote_branch);
+                result = delete_branch(workdir, branch, &config.base_branch, options.force, delete_remote);
+                journal_entry.branch_deleted = !branch_exists(workdir, branch);
+            }
+            None => {
+                println!("Worktree '{}' had no branch checked out, nothing to delete", worktree_name);
+            }
+        }
+    }
+    
+    *removed = Some(record_operation(repo, journal_entry));
+    
+    result
+}
+
+/// Lock a worktree so that `prune` and `pull --all` leave it alone
+pub fn lock_worktree(repo: &Repository, worktree_name: &str, reason: Option<&str>) -> Result<()> {
     use std::process::Command;
     
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    let repo_name = get_repository_name(repo)?;
-    let parent_dir = workdir.parent()
-        .context("Failed to get parent directory of repository")?;
+    let workdir = get_command_dir(repo);
+    let worktree_path = resolve_worktree_path(repo, worktree_name)?;
     
-    // Construct expected worktree path
-    let worktree_path = parent_dir.join(format!("{}_{}", repo_name, worktree_name));
+    if !worktree_path.exists() {
+        bail!("Worktree '{}' not found at {:?}", worktree_name, worktree_path);
+    }
+    
+    let mut args = vec!["worktree", "lock"];
+    if let Some(reason) = reason {
+        args.extend(["--reason", reason]);
+    }
+    args.push(worktree_path.to_str().unwrap());
+    
+    let output = Command::new("git")
+        .args(&args)
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git worktree lock command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to lock worktree: {}", stderr.trim());
+    }
+    
+    match reason {
+        Some(reason) => println!("Locked worktree '{}': {}", worktree_name, reason),
+        None => println!("Locked worktree '{}'", worktree_name),
+    }
+    
+    Ok(())
+}
+
+/// Unlock a worktree locked with `lock_worktree` or `git worktree lock`
+pub fn unlock_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
+    use std::process::Command;
+    
+    let workdir = get_command_dir(repo);
+    let worktree_path = resolve_worktree_path(repo, worktree_name)?;
     
-    // Check if worktree exists
     if !worktree_path.exists() {
         bail!("Worktree '{}' not found at {:?}", worktree_name, worktree_path);
     }
     
-    // Use git worktree remove command
     let output = Command::new("git")
-        .args(&["worktree", "remove", worktree_path.to_str().unwrap()])
+        .args(["worktree", "unlock", worktree_path.to_str().unwrap()])
         .current_dir(workdir)
         .output()
-        .context("Failed to execute git worktree remove command")?;
+        .context("Failed to execute git worktree unlock command")?;
     
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        
-        // Try with --force if it contains uncommitted changes
-        if stderr.contains("contains modified or untracked files") {
-            println!("Worktree contains uncommitted changes, removing with --force");
-            
-            let output = Command::new("git")
-                .args(&["worktree", "remove", "--force", worktree_path.to_str().unwrap()])
-                .current_dir(workdir)
-                .output()
-                .context("Failed to execute git worktree remove command")?;
-            
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                bail!("Failed to remove worktree: {}", stderr);
-            }
-        } else {
-            bail!("Failed to remove worktree: {}", stderr);
-        }
+        bail!("Failed to unlock worktree: {}", stderr.trim());
     }
     
-    println!("Removed worktree '{}' at {:?}", worktree_name, worktree_path);
+    println!("Unlocked worktree '{}'", worktree_name);
     
     Ok(())
 }
 
-/// Show status of all worktrees
-pub fn show_worktrees_status(repo: &Repository) -> Result<()> {
+/// A worktree entry as reported by `git worktree list --porcelain`
+#[derive(Debug, Clone)]
+pub struct WorktreeInfo {
+    pub path: PathBuf,
+    pub head: Option<String>,
+    pub branch: Option<String>,
+    pub is_bare: bool,
+    pub is_detached: bool,
+    /// Lock reason (empty if locked without one), or `None` if not locked
+    pub locked: Option<String>,
+    /// Why git considers the entry stale, or `None` if it is not prunable
+    pub prunable: Option<String>,
+}
+
+/// List the worktrees registered in the repository
+pub fn get_worktrees(repo: &Repository) -> Result<Vec<WorktreeInfo>> {
     use std::process::Command;
     
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
+    let workdir = get_command_dir(repo);
     
-    // Get list of worktrees
     let output = Command::new("git")
-        .args(&["worktree", "list", "--porcelain"])
+        .args(["worktree", "list", "--porcelain"])
         .current_dir(workdir)
         .output()
         .context("Failed to execute git worktree list command")?;
@@ -226,58 +876,153 @@ pub fn show_worktrees_status(repo: &Repository) -> Result<()> {
     }
     
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
-    
-    // Parse worktrees
+    Ok(parse_worktree_list(&stdout))
+}
+
+fn parse_worktree_list(porcelain: &str) -> Vec<WorktreeInfo> {
     let mut worktrees = Vec::new();
-    let mut i = 0;
-    while i < lines.len() {
-        if lines[i].starts_with("worktree ") {
-            let path = lines[i].strip_prefix("worktree ").unwrap_or("");
-            let mut branch = "detached";
-            
-            // Look for branch info
-            if i + 1 < lines.len() && lines[i + 1].starts_with("HEAD ") {
-                i += 1;
-            }
-            if i + 1 < lines.len() && lines[i + 1].starts_with("branch ") {
-                branch = lines[i + 1].strip_prefix("branch refs/heads/").unwrap_or("unknown");
-                i += 1;
-            }
-            
-            worktrees.push((path.to_string(), branch.to_string()));
+    let mut current: Option<WorktreeInfo> = None;
+    
+    for line in porcelain.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.extend(current.take());
+            current = Some(WorktreeInfo {
+                path: PathBuf::from(path),
+                head: None,
+                branch: None,
+                is_bare: false,
+                is_detached: false,
+                locked: None,
+                prunable: None,
+            });
+            continue;
+        }
+        
+        let Some(worktree) = current.as_mut() else {
+            continue;
+        };
+        
+        if let Some(head) = line.strip_prefix("HEAD ") {
+            worktree.head = Some(head.to_string());
+        } else if let Some(branch) = line.strip_prefix("branch ") {
+            let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+            worktree.branch = Some(branch.to_string());
+        } else if line == "bare" {
+            worktree.is_bare = true;
+        } else if line == "detached" {
+            worktree.is_detached = true;
+        } else if line == "locked" {
+            worktree.locked = Some(String::new());
+        } else if let Some(reason) = line.strip_prefix("locked ") {
+            worktree.locked = Some(reason.to_string());
+        } else if line == "prunable" {
+            worktree.prunable = Some(String::new());
+        } else if let Some(reason) = line.strip_prefix("prunable ") {
+            worktree.prunable = Some(reason.to_string());
         }
-        i += 1;
     }
+    worktrees.extend(current);
+    
+    worktrees
+}
+
+/// Which untracked files to include when computing worktree status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum UntrackedFiles {
+    /// Ignore untracked files
+    No,
+    /// Report untracked files, collapsing untracked directories
+    #[default]
+    Normal,
+    /// Report every untracked file, recursing into untracked directories
+    All,
+}
+
+/// Count the uncommitted changes in the worktree at `path`
+pub fn count_worktree_changes(path: &Path, untracked: UntrackedFiles) -> Result<usize> {
+    let worktree_repo = Repository::open(path)
+        .with_context(|| format!("Failed to open worktree at {:?}", path))?;
+    
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_ignored(false)
+        .include_untracked(untracked != UntrackedFiles::No)
+        .recurse_untracked_dirs(untracked == UntrackedFiles::All);
+    
+    let statuses = worktree_repo.statuses(Some(&mut options))
+        .with_context(|| format!("Failed to compute status of {:?}", path))?;
+    let index = worktree_repo.index()?;
+    
+    Ok(statuses.iter().filter(|entry| !dirty::is_sparse_omission(&index, entry)).count())
+}
+
+/// Compute the status of every worktree, spreading the work over a thread pool
+///
+/// The results are returned in the same order as `get_worktrees`.
+pub fn collect_worktree_statuses(
+    repo: &Repository,
+    untracked: UntrackedFiles,
+) -> Result<Vec<(WorktreeInfo, Result<usize>)>> {
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    let worktrees = get_worktrees(repo)?;
+    let results: Vec<Mutex<Option<Result<usize>>>> = worktrees.iter().map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    
+    let thread_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(worktrees.len().max(1));
+    
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(worktree) = worktrees.get(index) else {
+                        break;
+                    };
+                    
+                    let result = if worktree.is_bare {
+                        Ok(0)
+                    } else {
+                        count_worktree_changes(&worktree.path, untracked)
+                    };
+                    *results[index].lock().unwrap() = Some(result);
+                }
+            });
+        }
+    });
+    
+    Ok(worktrees
+        .into_iter()
+        .zip(results)
+        .map(|(worktree, result)| {
+            let result = result.into_inner().unwrap()
+                .unwrap_or_else(|| Err(anyhow::anyhow!("Status was not computed")));
+            (worktree, result)
+        })
+        .collect())
+}
+
+/// Show status of all worktrees
+pub fn show_worktrees_status(repo: &Repository, untracked: UntrackedFiles) -> Result<()> {
+    let statuses = collect_worktree_statuses(repo, untracked)?;
+    let metadata = Metadata::load(repo)?;
     
     // Display worktrees with status
     println!("{}", "Worktrees:".bold());
-    let main_path = workdir.to_string_lossy().trim_end_matches('/').to_string();
     
-    for (path, branch) in worktrees {
-        let normalized_path = path.trim_end_matches('/');
-        let is_main = normalized_path == main_path;
-        
-        // Check for uncommitted changes
-        let status_output = Command::new("git")
-            .args(&["status", "--porcelain"])
-            .current_dir(&path)
-            .output();
+    for (worktree, status) in statuses {
+        let path = worktree.path.to_string_lossy().to_string();
+        let branch = branch_label(&metadata, &worktree);
+        let is_main = is_main_worktree(repo, &worktree.path);
         
-        let status_msg = if let Ok(output) = status_output {
-            if output.status.success() {
-                let status_str = String::from_utf8_lossy(&output.stdout);
-                if status_str.trim().is_empty() {
-                    "clean".green().to_string()
-                } else {
-                    let change_count = status_str.lines().count();
-                    format!("{} uncommitted changes", change_count).yellow().to_string()
-                }
-            } else {
-                "unknown".red().to_string()
-            }
-        } else {
-            "error".red().to_string()
+        let status_msg = match status {
+            Ok(0) => "clean".green().to_string(),
+            Ok(change_count) => format!("{} uncommitted changes", change_count).yellow().to_string(),
+            Err(_) => "error".red().to_string(),
         };
         
         let display_path = if is_main {
@@ -286,81 +1031,82 @@ pub fn show_worktrees_status(repo: &Repository) -> Result<()> {
             path.yellow()
         };
         
-        println!("  {} [{}] - {}", display_path, branch.cyan(), status_msg);
+        if worktree.is_bare {
+            println!("  {} [bare]", display_path);
+            continue;
+        }
+        
+        println!("  {} [{}] - {}{}", display_path, branch.cyan(), status_msg, lock_suffix(&worktree));
+        
+        match submodule_report(&worktree.path) {
+            Ok(report) if !report.is_empty() => println!("    submodules: {}", report.summary().yellow()),
+            Ok(_) => {}
+            Err(e) => println!("    submodules: {}", format!("error: {}", e).red()),
+        }
     }
     
     Ok(())
 }
 
+/// Record a pull in the journal if it moved the worktree's HEAD
+fn record_pull(repo: &Repository, name: &str, path: &Path, branch: Option<&str>, before: Option<String>) {
+    let after = head_commit(path);
+    if after == before {
+        return;
+    }
+    
+    let mut entry = JournalEntry::new(Operation::Pull, name);
+    entry.path = Some(path.to_path_buf());
+    entry.branch = branch.map(str::to_string);
+    entry.before = before;
+    entry.after = after;
+    record_operation(repo, entry);
+}
+
 /// Pull changes in all worktrees
-pub fn pull_all_worktrees(repo: &Repository) -> Result<()> {
+///
+/// Locked worktrees are skipped unless `include_locked` is set.
+pub fn pull_all_worktrees(repo: &Repository, include_locked: bool) -> Result<()> {
     use std::process::Command;
     
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    
     println!("Pulling all worktrees from origin/main...");
     
-    // Get list of worktrees
-    let output = Command::new("git")
-        .args(&["worktree", "list", "--porcelain"])
-        .current_dir(workdir)
-        .output()
-        .context("Failed to execute git worktree list command")?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to list worktrees: {}", stderr);
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
-    
-    // Parse worktree paths
-    let mut worktree_paths = Vec::new();
-    let mut i = 0;
-    while i < lines.len() {
-        if lines[i].starts_with("worktree ") {
-            let path = lines[i].strip_prefix("worktree ").unwrap_or("");
-            let mut branch = "detached";
-            
-            // Look for branch info
-            if i + 1 < lines.len() && lines[i + 1].starts_with("HEAD ") {
-                i += 1;
-            }
-            if i + 1 < lines.len() && lines[i + 1].starts_with("branch ") {
-                branch = lines[i + 1].strip_prefix("branch refs/heads/").unwrap_or("unknown");
-                i += 1;
-            }
-            
-            worktree_paths.push((path.to_string(), branch.to_string()));
-        }
-        i += 1;
-    }
+    let worktrees = get_worktrees(repo)?;
+    let metadata = Metadata::load(repo)?;
     
     // Pull origin/main in each worktree
-    let main_path = workdir.to_string_lossy().trim_end_matches('/').to_string();
-    
-    for (path, branch) in worktree_paths {
-        let normalized_path = path.trim_end_matches('/');
-        let is_main = normalized_path == main_path;
+    for worktree in worktrees {
+        // A bare repository has no files to update
+        if worktree.is_bare {
+            continue;
+        }
+        
+        let path = worktree.path.to_string_lossy().to_string();
+        let branch = branch_label(&metadata, &worktree);
+        let is_main = is_main_worktree(repo, &worktree.path);
         
         let worktree_name = if is_main {
             "main".to_string()
         } else {
-            // Extract worktree name from path
-            std::path::Path::new(&path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or(&branch)
-                .to_string()
+            worktree_name(repo, &worktree.path).unwrap_or_else(|_| branch.clone())
         };
         
+        if worktree.locked.is_some() && !include_locked {
+            println!("  {} [{}]: {}{}", worktree_name.yellow(), branch.cyan(), "Skipped".yellow(), lock_suffix(&worktree));
+            continue;
+        }
+        
+        if worktree.is_detached {
+            println!("  {} [{}]: {}: detached HEAD, no branch to pull into", worktree_name.yellow(), branch.cyan(), "Skipped".yellow());
+            continue;
+        }
+        
         print!("  {} [{}]: ", worktree_name.yellow(), branch.cyan());
         
         // Pull from origin/main
+        let before = head_commit(Path::new(&path));
         let pull_output = Command::new("git")
-            .args(&["pull", "origin", "main"])
+            .args(["pull", "origin", "main"])
             .current_dir(&path)
             .output();
         
@@ -373,6 +1119,7 @@ pub fn pull_all_worktrees(repo: &Repository) -> Result<()> {
                     } else {
                         println!("{}", "Updated".green());
                     }
+                    record_pull(repo, &worktree_name, Path::new(&path), Some(&branch), before);
                 } else {
                     let stderr = String::from_utf8_lossy(&output.stderr);
                     if stderr.contains("Could not find remote") || stderr.contains("fatal: 'origin' does not appear to be a git repository") {
@@ -391,22 +1138,31 @@ pub fn pull_all_worktrees(repo: &Repository) -> Result<()> {
     Ok(())
 }
 
+/// Refuse to pull into a worktree with a detached HEAD, where a merge would
+/// leave commits on no branch
+fn refuse_detached_pull(repo: &Repository, worktree_name: &str, path: &Path) -> Result<()> {
+    let target = canonical(path);
+    
+    if let Some(worktree) = get_worktrees(repo)?.into_iter().find(|worktree| canonical(&worktree.path) == target)
+        && worktree.is_detached {
+        let label = branch_label(&Metadata::load(repo)?, &worktree);
+        bail!("Worktree '{}' is {}, with no branch to pull into; check out a branch with `git switch` first", worktree_name, label);
+    }
+    
+    Ok(())
+}
+
 /// Pull changes in a specific worktree
 pub fn pull_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
     use std::process::Command;
     
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    let repo_name = get_repository_name(repo)?;
-    let parent_dir = workdir.parent()
-        .context("Failed to get parent directory of repository")?;
-    
-    // Check if pulling main worktree
-    let worktree_path = if worktree_name == "main" {
-        workdir.to_path_buf()
+    // Check if pulling main worktree; a bare layout may have a worktree named "main" instead
+    let main_worktree = get_main_worktree(repo).filter(|_| worktree_name == "main");
+    let worktree_path = if let Some(main_worktree) = main_worktree {
+        main_worktree
     } else {
-        // Construct expected worktree path
-        let path = parent_dir.join(format!("{}_{}", repo_name, worktree_name));
+        // Find the worktree by name
+        let path = resolve_worktree_path(repo, worktree_name)?;
         
         // Check if worktree exists
         if !path.exists() {
@@ -414,12 +1170,14 @@ pub fn pull_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
         }
         path
     };
+    refuse_detached_pull(repo, worktree_name, &worktree_path)?;
     
     println!("Pulling worktree '{}' from origin/main...", worktree_name);
     
     // Execute git pull from origin/main
+    let before = head_commit(&worktree_path);
     let output = Command::new("git")
-        .args(&["pull", "origin", "main"])
+        .args(["pull", "origin", "main"])
         .current_dir(&worktree_path)
         .output()
         .context("Failed to execute git pull command")?;
@@ -431,6 +1189,8 @@ pub fn pull_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
         } else {
             println!("{}: {}", worktree_name.yellow(), "Updated".green());
         }
+        let branch = branch_of_worktree(repo, &worktree_path)?;
+        record_pull(repo, worktree_name, &worktree_path, branch.as_deref(), before);
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
         if stderr.contains("Could not find remote") || stderr.contains("fatal: 'origin' does not appear to be a git repository") {
@@ -448,13 +1208,15 @@ pub fn pull_current_worktree(repo: &Repository) -> Result<()> {
     use std::process::Command;
     
     let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
+        .context("Not inside a worktree; pass a worktree name or --all")?;
+    refuse_detached_pull(repo, "current", workdir)?;
     
     println!("Pulling current worktree from origin/main...");
     
     // Execute git pull from origin/main
+    let before = head_commit(workdir);
     let output = Command::new("git")
-        .args(&["pull", "origin", "main"])
+        .args(["pull", "origin", "main"])
         .current_dir(workdir)
         .output()
         .context("Failed to execute git pull command")?;
@@ -466,6 +1228,9 @@ pub fn pull_current_worktree(repo: &Repository) -> Result<()> {
         } else {
             println!("{}", "Updated".green());
         }
+        let name = workdir.file_name().and_then(|n| n.to_str()).unwrap_or("current");
+        let branch = branch_of_worktree(repo, workdir)?;
+        record_pull(repo, name, workdir, branch.as_deref(), before);
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
         if stderr.contains("Could not find remote") || stderr.contains("fatal: 'origin' does not appear to be a git repository") {
@@ -477,168 +1242,3 @@ pub fn pull_current_worktree(repo: &Repository) -> Result<()> {
     
     Ok(())
 }
-
-/// Prune merged worktrees
-pub fn prune_merged_worktrees(repo: &Repository, dry_run: bool, force: bool) -> Result<()> {
-    use std::process::Command;
-    use std::io::{self, Write};
-    
-    let workdir = repo.workdir()
-        .context("Failed to get repository working directory")?;
-    let repo_name = get_repository_name(repo)?;
-    
-    // Get list of worktrees
-    let output = Command::new("git")
-        .args(&["worktree", "list", "--porcelain"])
-        .current_dir(workdir)
-        .output()
-        .context("Failed to execute git worktree list command")?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Failed to list worktrees: {}", stderr);
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
-    
-    // Parse worktrees and check which branches are merged
-    let main_path = workdir.to_string_lossy().trim_end_matches('/').to_string();
-    let mut merged_worktrees = Vec::new();
-    let mut i = 0;
-    
-    while i < lines.len() {
-        if lines[i].starts_with("worktree ") {
-            let path = lines[i].strip_prefix("worktree ").unwrap_or("");
-            let normalized_path = path.trim_end_matches('/');
-            
-            // Skip main worktree
-            if normalized_path == main_path {
-                i += 1;
-                continue;
-            }
-            
-            // Look for branch info
-            if i + 1 < lines.len() && lines[i + 1].starts_with("HEAD ") {
-                i += 1;
-            }
-            if i + 1 < lines.len() && lines[i + 1].starts_with("branch ") {
-                let branch = lines[i + 1].strip_prefix("branch refs/heads/").unwrap_or("unknown");
-                i += 1;
-                
-                // Check if branch is merged to main
-                let merged_output = Command::new("git")
-                    .args(&["branch", "--merged", "main"])
-                    .current_dir(workdir)
-                    .output();
-                
-                if let Ok(output) = merged_output {
-                    if output.status.success() {
-                        let merged_branches = String::from_utf8_lossy(&output.stdout);
-                        if merged_branches.lines().any(|line| line.trim() == branch || line.trim() == format!("* {}", branch)) {
-                            // Extract worktree name from path
-                            let worktree_name = std::path::Path::new(path)
-                                .file_name()
-                                .and_then(|n| n.to_str())
-                                .unwrap_or(branch)
-                                .to_string();
-                            
-                            // Remove repo name prefix if present
-                            let worktree_name = if worktree_name.starts_with(&format!("{}_", repo_name)) {
-                                worktree_name.strip_prefix(&format!("{}_", repo_name)).unwrap_or(&worktree_name).to_string()
-                            } else {
-                                worktree_name
-                            };
-                            
-                            merged_worktrees.push((path.to_string(), branch.to_string(), worktree_name));
-                        }
-                    }
-                }
-            }
-        }
-        i += 1;
-    }
-    
-    if merged_worktrees.is_empty() {
-        println!("No worktrees to prune");
-        return Ok(());
-    }
-    
-    if dry_run {
-        println!("Would prune {} merged worktree{}:", 
-                 merged_worktrees.len(), 
-                 if merged_worktrees.len() == 1 { "" } else { "s" });
-        for (path, branch, name) in &merged_worktrees {
-            println!("  {} [{}] at {}", name.yellow(), branch.cyan(), path);
-        }
-        return Ok(());
-    }
-    
-    // Show worktrees to be pruned
-    println!("Found {} merged worktree{} to prune:", 
-             merged_worktrees.len(), 
-             if merged_worktrees.len() == 1 { "" } else { "s" });
-    for (path, branch, name) in &merged_worktrees {
-        println!("  {} [{}] at {}", name.yellow(), branch.cyan(), path);
-    }
-    
-    // Ask for confirmation unless --force is used
-    if !force {
-        print!("\nPrune these worktrees? [y/N] ");
-        io::stdout().flush()?;
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if !input.trim().eq_ignore_ascii_case("y") {
-            println!("Cancelled");
-            return Ok(());
-        }
-    }
-    
-    // Prune each worktree
-    let pruned_count = merged_worktrees.len();
-    for (path, _, name) in merged_worktrees {
-        print!("Pruning {}... ", name.yellow());
-        
-        let output = Command::new("git")
-            .args(&["worktree", "remove", &path])
-            .current_dir(workdir)
-            .output()
-            .context("Failed to execute git worktree remove command")?;
-        
-        if output.status.success() {
-            println!("{}", "done".green());
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            
-            // Try with --force if it contains uncommitted changes
-            if stderr.contains("contains modified or untracked files") {
-                print!("has uncommitted changes, removing with --force... ");
-                
-                let output = Command::new("git")
-                    .args(&["worktree", "remove", "--force", &path])
-                    .current_dir(workdir)
-                    .output()
-                    .context("Failed to execute git worktree remove command")?;
-                
-                if output.status.success() {
-                    println!("{}", "done".green());
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    println!("{}", "failed".red());
-                    eprintln!("  Error: {}", stderr.trim());
-                }
-            } else {
-                println!("{}", "failed".red());
-                eprintln!("  Error: {}", stderr.trim());
-            }
-        }
-    }
-    
-    println!("\nPruned {} worktree{}", 
-             pruned_count, 
-             if pruned_count == 1 { "" } else { "s" });
-    
-    Ok(())
-}
\ No newline at end of file
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::env;
+use std::path::PathBuf;
 
 /// A simple Git worktree manager
 #[derive(Parser)]
@@ -17,25 +18,258 @@ enum Commands {
     Add {
         /// Name of the worktree
         name: String,
+        /// Open the new worktree in the configured editor
+        #[arg(long)]
+        open: bool,
+        /// Create a tmux session for the new worktree and attach to it
+        #[arg(long)]
+        tmux: bool,
+        /// Initialise and check out submodules (always done with `submodules = true`)
+        #[arg(long)]
+        submodules: bool,
+        /// Check out only the directories of a sparse profile from the configuration
+        #[arg(long, value_name = "PROFILE")]
+        sparse: Option<String>,
+        /// Check out a commit, tag or branch with a detached HEAD instead of creating a branch
+        #[arg(long, value_name = "REF")]
+        detach: Option<String>,
     },
     /// List all worktrees
-    List,
+    List {
+        /// List the worktrees of every repository gwtr has been used in
+        #[arg(long)]
+        global: bool,
+    },
     /// Remove a worktree
     Remove {
         /// Name of the worktree to remove
         name: String,
+        #[command(flatten)]
+        branch: BranchDeletionArgs,
+        /// Remove even with uncommitted changes, and delete the branch even if unmerged
+        #[arg(long, short)]
+        force: bool,
     },
     /// Show status of all worktrees
-    Status,
+    Status {
+        /// Which untracked files to report
+        #[arg(long, short = 'u', value_enum, default_value_t = gwtr::UntrackedFiles::Normal)]
+        untracked_files: gwtr::UntrackedFiles,
+        /// Show the worktrees of every repository gwtr has been used in
+        #[arg(long)]
+        global: bool,
+    },
     /// Pull changes in worktrees
     Pull {
         /// Pull all worktrees
         #[arg(long, short)]
         all: bool,
+        /// Also pull locked worktrees with --all
+        #[arg(long, requires = "all")]
+        include_locked: bool,
         /// Specific worktree name to pull (optional)
         name: Option<String>,
     },
+    /// Manage worktrees across the repositories of a workspace
+    Ws {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    /// Manage uncommitted work saved from deleted worktrees
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    /// Recreate a deleted worktree with its saved uncommitted changes
+    Restore {
+        /// Trash entry (`<name>/<timestamp>`), or a worktree name for its newest entry
+        entry: String,
+    },
+    /// Lock a worktree so that prune and pull --all skip it
+    Lock {
+        /// Name of the worktree to lock
+        name: String,
+        /// Why the worktree is locked
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Unlock a worktree
+    Unlock {
+        /// Name of the worktree to unlock
+        name: String,
+    },
+    /// Rename a worktree, moving it to the path for its new name
+    #[command(alias = "move")]
+    Rename {
+        /// Current name of the worktree
+        old: String,
+        /// New name of the worktree
+        new: String,
+        /// Also rename the worktree's branch
+        #[arg(long)]
+        branch: bool,
+    },
+    /// Clone a repository into a worktree layout
+    Clone {
+        /// URL of the repository to clone
+        url: String,
+        /// Directory to clone into (defaults to the repository name)
+        directory: Option<PathBuf>,
+        /// Layout to create (defaults to `layout` in the user configuration, or main)
+        #[arg(long, value_enum)]
+        layout: Option<gwtr::Layout>,
+    },
+    /// Write a .gwtr.toml with detected defaults
+    Init {
+        /// Also move existing linked worktrees to the paths the layout template gives
+        #[arg(long)]
+        migrate: bool,
+    },
+    /// Register a worktree created outside gwtr under a name
+    Adopt {
+        /// Path of the existing worktree
+        path: PathBuf,
+        /// Name to address the worktree by (defaults to its directory name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Also move the worktree to the path for its name
+        #[arg(long)]
+        relocate: bool,
+    },
+    /// Open a worktree in the configured editor
+    Open {
+        /// Name of the worktree (defaults to the current one)
+        name: Option<String>,
+    },
+    /// Change which directories a sparse worktree checks out
+    Sparse {
+        /// Name of the worktree
+        name: String,
+        /// Add the directories, or replace the checked out ones with them
+        #[arg(value_enum)]
+        action: gwtr::SparseAction,
+        /// Directories to check out
+        #[arg(required = true)]
+        directories: Vec<String>,
+    },
+    /// Create or attach to the tmux session of a worktree
+    Tmux {
+        /// Name of the worktree
+        name: String,
+    },
+    /// Print the environment of a worktree, including its ports
+    Env {
+        /// Name of the worktree
+        name: String,
+        /// Also write it to the configured `env_file` (`.env` by default) in the worktree
+        #[arg(long)]
+        write: bool,
+    },
+    /// Create an auto-named temporary worktree that expires
+    Tmp {
+        /// How long the worktree lives (e.g. 2d, 12h); defaults to `tmp_ttl` (1d)
+        #[arg(long, value_name = "DURATION", value_parser = gwtr::parse_duration)]
+        ttl: Option<std::time::Duration>,
+        /// Commit, tag or branch the new branch starts at (defaults to HEAD)
+        #[arg(long, value_name = "REF")]
+        from: Option<String>,
+    },
+    /// Show disk usage of worktrees
+    Du {
+        /// Worktrees to include (defaults to all)
+        names: Vec<String>,
+        /// Column to sort by
+        #[arg(long, value_enum, default_value_t = gwtr::DuSort::Total)]
+        sort: gwtr::DuSort,
+        /// Delete ignored directories matching `artifact_patterns` (target, node_modules by default)
+        #[arg(long)]
+        clean_artifacts: bool,
+        /// Skip confirmation prompt
+        #[arg(long, short)]
+        force: bool,
+    },
+    /// Check for broken or stale worktree state
+    Doctor,
+    /// Fix what `gwtr doctor` finds where it is safe to do so
+    Repair,
+    /// Show the history of gwtr operations
+    Log {
+        /// Only show the most recent entries
+        #[arg(long, short = 'n')]
+        limit: Option<usize>,
+    },
+    /// Reverse the most recent operation
+    Undo,
     /// Prune merged worktrees
+    Prune {
+        /// Show what would be pruned without actually removing
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip confirmation prompt
+        #[arg(long, short)]
+        force: bool,
+        #[command(flatten)]
+        branch: BranchDeletionArgs,
+        /// Delete branches with --delete-branch even if they are not merged into the base branch
+        #[arg(long)]
+        delete_unmerged: bool,
+        /// Fetch with --prune and also prune worktrees whose upstream branch was deleted
+        #[arg(long)]
+        gone: bool,
+        /// Also prune worktrees whose last commit is older than this (e.g. 30d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = gwtr::parse_duration)]
+        older_than: Option<std::time::Duration>,
+        /// Also prune worktrees with no file modified within this period (e.g. 30d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = gwtr::parse_duration)]
+        inactive_for: Option<std::time::Duration>,
+        /// Also prune worktrees that have uncommitted changes
+        #[arg(long)]
+        include_dirty: bool,
+        /// Also prune locked worktrees
+        #[arg(long)]
+        include_locked: bool,
+        /// Also prune temporary worktrees whose time to live has passed
+        #[arg(long)]
+        expired: bool,
+        /// Prune the worktrees of every repository gwtr has been used in
+        #[arg(long)]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommands {
+    /// List saved trash entries
+    List,
+    /// Permanently delete a trash entry
+    Drop {
+        /// Trash entry (`<name>/<timestamp>`), or a worktree name for its newest entry
+        entry: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceCommands {
+    /// Create a worktree and branch in every repository of the workspace
+    Add {
+        /// Name of the worktrees and branches
+        name: String,
+    },
+    /// Show status of the worktrees of every repository
+    Status {
+        /// Which untracked files to report
+        #[arg(long, short = 'u', value_enum, default_value_t = gwtr::UntrackedFiles::Normal)]
+        untracked_files: gwtr::UntrackedFiles,
+    },
+    /// Remove a worktree from every repository that has it
+    Remove {
+        /// Name of the worktrees to remove
+        name: String,
+        /// Remove even with uncommitted changes
+        #[arg(long, short)]
+        force: bool,
+    },
+    /// Remove features merged in every repository
     Prune {
         /// Show what would be pruned without actually removing
         #[arg(long)]
@@ -46,6 +280,36 @@ enum Commands {
     },
 }
 
+/// Flags controlling whether the branch of a removed worktree is deleted
+#[derive(Args)]
+struct BranchDeletionArgs {
+    /// Delete the local branch after removing the worktree
+    #[arg(long, conflicts_with = "keep_branch")]
+    delete_branch: bool,
+    /// Keep the local branch even if `delete_branch` is set in .gwtr.toml
+    #[arg(long)]
+    keep_branch: bool,
+    /// Also delete the remote branch the local branch tracks
+    #[arg(long)]
+    delete_remote: bool,
+}
+
+impl BranchDeletionArgs {
+    fn delete_branch(&self) -> Option<bool> {
+        if self.delete_branch {
+            Some(true)
+        } else if self.keep_branch {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    
+    fn delete_remote_branch(&self) -> Option<bool> {
+        self.delete_remote.then_some(true)
+    }
+}
+
 fn main() {
     if let Err(e) = run() {
         eprintln!("Error: {}", e);
@@ -56,47 +320,85 @@ fn main() {
 fn run() -> Result<()> {
     let cli = Cli::parse();
     
+    // Clean up expired temporary worktrees, except when only previewing a prune or
+    // working with the journal and the trash, whose last entry must stay the user's own
+    if !matches!(&cli.command, Some(Commands::Prune { dry_run: true, .. } | Commands::Undo | Commands::Log { .. }
+                                     | Commands::Restore { .. } | Commands::Trash { .. }))
+        && let Ok(current_dir) = env::current_dir() {
+        gwtr::expire_temporaries(&current_dir);
+    }
+    
     match &cli.command {
-        Some(Commands::Add { name }) => {
+        Some(Commands::Add { name, open, tmux, submodules, sparse, detach }) => {
             // Validate git repository
             let current_dir = env::current_dir()?;
             let repo = gwtr::ensure_git_repository(&current_dir)?;
             
             // Create worktree
-            gwtr::create_worktree(&repo, name)?;
-        }
-        Some(Commands::List) => {
-            // Validate git repository
-            let current_dir = env::current_dir()?;
-            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            let options = gwtr::AddOptions {
+                sparse: sparse.clone(),
+                detach: detach.clone(),
+                ..Default::default()
+            };
+            let path = gwtr::create_worktree_with(&repo, name, &options)?;
             
-            // List worktrees
-            gwtr::list_worktrees(&repo)?;
+            if *submodules && !gwtr::Config::load(&repo)?.submodules {
+                gwtr::update_submodules(&repo, &path)?;
+            }
+            if *open {
+                gwtr::open_worktree(&repo, Some(name))?;
+            }
+            if *tmux {
+                gwtr::tmux_session(&repo, name)?;
+            }
+        }
+        Some(Commands::List { global }) => {
+            if *global {
+                // List worktrees of all registered repositories
+                gwtr::for_each_repository(gwtr::list_worktrees)?;
+            } else {
+                // Validate git repository
+                let current_dir = env::current_dir()?;
+                let repo = gwtr::ensure_git_repository(&current_dir)?;
+                
+                // List worktrees
+                gwtr::list_worktrees(&repo)?;
+            }
         }
-        Some(Commands::Remove { name }) => {
+        Some(Commands::Remove { name, branch, force }) => {
             // Validate git repository
             let current_dir = env::current_dir()?;
             let repo = gwtr::ensure_git_repository(&current_dir)?;
             
             // Remove worktree
-            gwtr::remove_worktree(&repo, name)?;
+            let options = gwtr::RemoveOptions {
+                delete_branch: branch.delete_branch(),
+                delete_remote_branch: branch.delete_remote_branch(),
+                force: *force,
+            };
+            gwtr::remove_worktree(&repo, name, &options)?;
         }
-        Some(Commands::Status) => {
-            // Validate git repository
-            let current_dir = env::current_dir()?;
-            let repo = gwtr::ensure_git_repository(&current_dir)?;
-            
-            // Show worktrees status
-            gwtr::show_worktrees_status(&repo)?;
+        Some(Commands::Status { untracked_files, global }) => {
+            if *global {
+                // Show worktrees status of all registered repositories
+                gwtr::for_each_repository(|repo| gwtr::show_worktrees_status(repo, *untracked_files))?;
+            } else {
+                // Validate git repository
+                let current_dir = env::current_dir()?;
+                let repo = gwtr::ensure_git_repository(&current_dir)?;
+                
+                // Show worktrees status
+                gwtr::show_worktrees_status(&repo, *untracked_files)?;
+            }
         }
-        Some(Commands::Pull { all, name }) => {
+        Some(Commands::Pull { all, include_locked, name }) => {
             // Validate git repository
             let current_dir = env::current_dir()?;
             let repo = gwtr::ensure_git_repository(&current_dir)?;
             
             // Pull worktrees
             if *all {
-                gwtr::pull_all_worktrees(&repo)?;
+                gwtr::pull_all_worktrees(&repo, *include_locked)?;
             } else if let Some(worktree_name) = name {
                 gwtr::pull_worktree(&repo, worktree_name)?;
             } else {
@@ -104,13 +406,186 @@ fn run() -> Result<()> {
                 gwtr::pull_current_worktree(&repo)?;
             }
         }
-        Some(Commands::Prune { dry_run, force }) => {
+        Some(Commands::Prune { dry_run, force, branch, delete_unmerged, gone, older_than, inactive_for, include_dirty, include_locked, expired, global }) => {
+            let options = gwtr::PruneOptions {
+                dry_run: *dry_run,
+                force: *force,
+                delete_unmerged: *delete_unmerged,
+                gone: *gone,
+                older_than: *older_than,
+                inactive_for: *inactive_for,
+                include_dirty: *include_dirty,
+                include_locked: *include_locked,
+                expired: *expired,
+                delete_branch: branch.delete_branch(),
+                delete_remote_branch: branch.delete_remote_branch(),
+            };
+            
+            if *global {
+                // Prune merged worktrees of all registered repositories
+                gwtr::for_each_repository(|repo| gwtr::prune_merged_worktrees(repo, &options))?;
+            } else {
+                // Validate git repository
+                let current_dir = env::current_dir()?;
+                let repo = gwtr::ensure_git_repository(&current_dir)?;
+                
+                // Prune merged worktrees
+                gwtr::prune_merged_worktrees(&repo, &options)?;
+            }
+        }
+        Some(Commands::Ws { command }) => {
+            // Find workspace manifest
+            let current_dir = env::current_dir()?;
+            let workspace = gwtr::Workspace::discover(&current_dir)?;
+            
+            match command {
+                WorkspaceCommands::Add { name } => gwtr::workspace_add(&workspace, name)?,
+                WorkspaceCommands::Status { untracked_files } => gwtr::workspace_status(&workspace, *untracked_files)?,
+                WorkspaceCommands::Remove { name, force } => gwtr::workspace_remove(&workspace, name, *force)?,
+                WorkspaceCommands::Prune { dry_run, force } => gwtr::workspace_prune(&workspace, *dry_run, *force)?,
+            }
+        }
+        Some(Commands::Trash { command }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            match command {
+                TrashCommands::List => gwtr::list_trash(&repo)?,
+                TrashCommands::Drop { entry } => gwtr::drop_trash(&repo, entry)?,
+            }
+        }
+        Some(Commands::Restore { entry }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Restore worktree from trash
+            gwtr::restore_from_trash(&repo, entry)?;
+        }
+        Some(Commands::Lock { name, reason }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Lock worktree
+            gwtr::lock_worktree(&repo, name, reason.as_deref())?;
+        }
+        Some(Commands::Unlock { name }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Unlock worktree
+            gwtr::unlock_worktree(&repo, name)?;
+        }
+        Some(Commands::Rename { old, new, branch }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Rename worktree
+            gwtr::rename_worktree(&repo, old, new, *branch)?;
+        }
+        Some(Commands::Clone { url, directory, layout }) => {
+            // Clone repository
+            gwtr::clone_repository(url, directory.as_deref(), *layout)?;
+        }
+        Some(Commands::Init { migrate }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Write configuration
+            gwtr::init_repository(&repo, *migrate)?;
+        }
+        Some(Commands::Adopt { path, name, relocate }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Adopt worktree
+            gwtr::adopt_worktree(&repo, path, name.as_deref(), *relocate)?;
+        }
+        Some(Commands::Open { name }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Open the worktree in the editor
+            gwtr::open_worktree(&repo, name.as_deref())?;
+        }
+        Some(Commands::Sparse { name, action, directories }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Change the sparse checkout
+            gwtr::sparse_worktree(&repo, name, *action, directories)?;
+        }
+        Some(Commands::Tmux { name }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Create or attach to the session
+            gwtr::tmux_session(&repo, name)?;
+        }
+        Some(Commands::Env { name, write }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Print the worktree environment
+            gwtr::show_env(&repo, name, *write)?;
+        }
+        Some(Commands::Tmp { ttl, from }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Create temporary worktree
+            gwtr::create_temporary_worktree(&repo, *ttl, from.as_deref())?;
+        }
+        Some(Commands::Du { names, sort, clean_artifacts, force }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Show disk usage
+            gwtr::show_disk_usage(&repo, names, *sort, *clean_artifacts, *force)?;
+        }
+        Some(Commands::Doctor) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Diagnose worktrees
+            gwtr::run_doctor(&repo)?;
+        }
+        Some(Commands::Repair) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Repair worktrees
+            gwtr::repair_worktrees(&repo)?;
+        }
+        Some(Commands::Log { limit }) => {
+            // Validate git repository
+            let current_dir = env::current_dir()?;
+            let repo = gwtr::ensure_git_repository(&current_dir)?;
+            
+            // Show operation journal
+            gwtr::show_journal(&repo, *limit)?;
+        }
+        Some(Commands::Undo) => {
             // Validate git repository
             let current_dir = env::current_dir()?;
             let repo = gwtr::ensure_git_repository(&current_dir)?;
             
-            // Prune merged worktrees
-            gwtr::prune_merged_worktrees(&repo, *dry_run, *force)?;
+            // Undo the most recent operation
+            gwtr::undo_last_operation(&repo)?;
         }
         None => {
             // This shouldn't happen with arg_required_else_help
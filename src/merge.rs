@@ -0,0 +1,91 @@
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use crate::git;
+
+/// The rule that identified a branch as merged into the base branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeRule {
+    /// The branch tip is an ancestor of the base branch (regular merge or fast-forward)
+    Ancestor,
+    /// Every commit on the branch has a patch-equivalent commit upstream (rebase merge)
+    PatchEquivalent,
+    /// The branch tip has the same tree as a commit on the base branch
+    IdenticalTree,
+    /// The combined changes of the branch were applied upstream as one commit (squash merge)
+    Squashed,
+}
+
+impl fmt::Display for MergeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            MergeRule::Ancestor => "merged",
+            MergeRule::PatchEquivalent => "rebase-merged",
+            MergeRule::IdenticalTree => "identical tree",
+            MergeRule::Squashed => "squash-merged",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// Whether `git cherry` reports every commit of `head` as already applied to `upstream`
+fn all_patches_upstream(dir: &Path, upstream: &str, head: &str) -> Result<bool> {
+    let cherry = git(dir, &["cherry", upstream, head])?;
+    Ok(!cherry.is_empty() && cherry.lines().all(|line| line.starts_with('-')))
+}
+
+/// Determine whether `branch` has been merged into `base`, and by which rule
+///
+/// Besides plain ancestry this recognises branches merged through rebase or squash
+/// merges, where the branch commits never become part of the base history.
+pub fn detect_merge(dir: &Path, branch: &str, base: &str) -> Result<Option<MergeRule>> {
+    let ancestor = Command::new("git")
+        .args(["merge-base", "--is-ancestor", branch, base])
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git merge-base")?;
+    match ancestor.status.code() {
+        Some(0) => return Ok(Some(MergeRule::Ancestor)),
+        Some(1) => {}
+        _ => {
+            let stderr = String::from_utf8_lossy(&ancestor.stderr);
+            bail!("Failed to compare '{}' with '{}': {}", branch, base, stderr.trim());
+        }
+    }
+    
+    if all_patches_upstream(dir, base, branch)? {
+        return Ok(Some(MergeRule::PatchEquivalent));
+    }
+    
+    let merge_base = git(dir, &["merge-base", base, branch])?;
+    let branch_tree = git(dir, &["rev-parse", &format!("{}^{{tree}}", branch)])?;
+    
+    let base_trees = git(dir, &["log", "--format=%T", base, &format!("^{}", merge_base)])?;
+    if base_trees.lines().any(|tree| tree == branch_tree) {
+        return Ok(Some(MergeRule::IdenticalTree));
+    }
+    
+    // Squash the branch into a single commit on top of the merge base and check
+    // whether an equivalent patch exists upstream. The identity is irrelevant to
+    // patch ids, so a fixed one avoids depending on the user's configuration.
+    let output = Command::new("git")
+        .args(["commit-tree", &branch_tree, "-p", &merge_base, "-m", "gwtr squash check"])
+        .env("GIT_AUTHOR_NAME", "gwtr")
+        .env("GIT_AUTHOR_EMAIL", "gwtr@localhost")
+        .env("GIT_COMMITTER_NAME", "gwtr")
+        .env("GIT_COMMITTER_EMAIL", "gwtr@localhost")
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git commit-tree")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to squash '{}': {}", branch, stderr.trim());
+    }
+    let squashed = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if all_patches_upstream(dir, base, &squashed)? {
+        return Ok(Some(MergeRule::Squashed));
+    }
+    
+    Ok(None)
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::{SparseCheckout, Temporary, canonical, get_common_dir};
+
+/// What gwtr knows about a worktree it manages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeMetadata {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Ref a detached worktree was created at, e.g. a release tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detached_from: Option<String>,
+    /// Port block allocated to the worktree, freed when its entry is removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ports: Option<PortBlock>,
+    /// Sparse checkout the worktree was restricted to by gwtr
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sparse: Option<SparseCheckout>,
+    /// Set for worktrees created by `gwtr tmp`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temporary: Option<Temporary>,
+}
+
+/// A range of `count` consecutive ports starting at `start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortBlock {
+    pub start: u16,
+    pub count: u16,
+}
+
+impl PortBlock {
+    /// The last port of the block
+    pub fn end(&self) -> u16 {
+        self.start + self.count - 1
+    }
+    
+    /// Whether the two blocks share a port
+    pub fn overlaps(&self, other: &PortBlock) -> bool {
+        self.start <= other.end() && other.start <= self.end()
+    }
+}
+
+/// Metadata about the worktrees of a repository, keyed by worktree name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    pub worktrees: BTreeMap<String, WorktreeMetadata>,
+}
+
+/// Location of the metadata file, shared by all worktrees of the repository
+pub fn metadata_path(repo: &Repository) -> PathBuf {
+    get_common_dir(repo).join("gwtr").join("worktrees.json")
+}
+
+impl Metadata {
+    /// Load the metadata, or an empty store if none was saved yet
+    pub fn load(repo: &Repository) -> Result<Metadata> {
+        let path = metadata_path(repo);
+        if !path.exists() {
+            return Ok(Metadata::default());
+        }
+        
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?}", path))
+    }
+    
+    /// Save the metadata atomically, so readers never see a partially written file
+    pub fn save(&self, repo: &Repository) -> Result<()> {
+        let path = metadata_path(repo);
+        let dir = path.parent().context("Metadata path has no parent directory")?;
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {:?}", dir))?;
+        
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {:?}", temp_path))?;
+        std::fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to replace {:?}", path))
+    }
+    
+    /// The name and entry of the worktree at `path`, if gwtr tracks it
+    pub fn entry_for_path(&self, path: &Path) -> Option<(&str, &WorktreeMetadata)> {
+        let path = canonical(path);
+        self.worktrees.iter()
+            .find(|(_, entry)| canonical(&entry.path) == path)
+            .map(|(name, entry)| (name.as_str(), entry))
+    }
+    
+    /// Load, modify and save the metadata in one step
+    pub fn update<F: FnOnce(&mut Metadata)>(repo: &Repository, f: F) -> Result<()> {
+        let mut metadata = Metadata::load(repo)?;
+        f(&mut metadata);
+        metadata.save(repo)
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result, bail};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ports::{quote, worktree_env_at};
+use crate::{Config, resolve_worktree_path, worktree_name};
+
+/// The editor command template: `editor` from `.gwtr.toml`, then from the
+/// user configuration, then `$VISUAL` or `$EDITOR`
+fn editor_template(repo: &Repository) -> Result<String> {
+    if let Some(editor) = Config::load(repo)?.editor {
+        return Ok(editor);
+    }
+    if let Some(editor) = Config::load_user()?.editor {
+        return Ok(editor);
+    }
+    
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(std::env::var_os)
+        .map(|editor| editor.to_string_lossy().to_string())
+        .find(|editor| !editor.trim().is_empty())
+        .context("No editor configured; set `editor` in .gwtr.toml or ~/.config/gwtr/config.toml, or $EDITOR")
+}
+
+/// Run the editor command for the worktree at `path`
+///
+/// `{path}` and `{name}` in the template are replaced by the quoted worktree path
+/// and name; without `{path}`, the path is appended. The command runs in a shell
+/// so templates such as `$EDITOR {path}` work, with the worktree's environment
+/// from [`crate::worktree_env`] set.
+pub(crate) fn open_path(repo: &Repository, name: &str, path: &Path) -> Result<()> {
+    let template = editor_template(repo)?;
+    let env = worktree_env_at(repo, name, path)?;
+    let quoted_path = quote(&path.to_string_lossy());
+    let command = if template.contains("{path}") {
+        template.replace("{path}", &quoted_path).replace("{name}", &quote(name))
+    } else {
+        format!("{} {}", template.replace("{name}", &quote(name)), quoted_path)
+    };
+    
+    let status = Command::new("sh")
+        .args(["-c", &command])
+        .current_dir(path)
+        .envs(env)
+        .status()
+        .with_context(|| format!("Failed to run editor command `{}`", command))?;
+    if !status.success() {
+        bail!("Editor command `{}` failed with {}", command, status);
+    }
+    
+    Ok(())
+}
+
+/// Open a worktree in the configured editor, or the current worktree if no name is given
+pub fn open_worktree(repo: &Repository, name: Option<&str>) -> Result<()> {
+    let (name, path) = match name {
+        Some(name) => (name.to_string(), resolve_worktree_path(repo, name)?),
+        None => {
+            let workdir = repo.workdir().context("Not inside a worktree; pass a worktree name")?;
+            // Drop the trailing slash libgit2 leaves on the path
+            let workdir: PathBuf = workdir.components().collect();
+            (worktree_name(repo, &workdir)?, workdir)
+        }
+    };
+    if !path.exists() {
+        bail!("Worktree '{}' not found at {:?}", name, path);
+    }
+    
+    open_path(repo, &name, &path)
+}
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::path::Path;
+
+use crate::{
+    Config, Metadata, PortBlock, Registry, WorktreeMetadata, branch_of_worktree, get_common_dir,
+    get_repository_name, resolve_worktree_path,
+};
+
+/// Lines delimiting the part of the env file that gwtr owns
+const ENV_BEGIN: &str = "# >>> gwtr >>>";
+const ENV_END: &str = "# <<< gwtr <<<";
+
+/// Port blocks held by the worktrees of every other repository gwtr knows about,
+/// so that dev servers of different projects do not collide either
+fn ports_in_other_repositories(repo: &Repository) -> Vec<PortBlock> {
+    let own = get_common_dir(repo).canonicalize().ok();
+    let Ok(registry) = Registry::load() else {
+        return Vec::new();
+    };
+    
+    registry.repositories.iter()
+        .filter_map(|root| Repository::open(root).ok())
+        .filter(|other| get_common_dir(other).canonicalize().ok() != own)
+        .filter_map(|other| Metadata::load(&other).ok())
+        .flat_map(|metadata| metadata.worktrees.into_values().filter_map(|entry| entry.ports))
+        .collect()
+}
+
+/// Allocate the lowest free port block to a worktree, or return the block it already has
+///
+/// A block is free when no worktree of this or any other registered repository
+/// holds a port in it. Blocks are freed by dropping the worktree's metadata,
+/// which `remove` and `prune` do.
+pub fn allocate_ports(repo: &Repository, worktree_name: &str) -> Result<PortBlock> {
+    let config = Config::load(repo)?;
+    if config.port_block_size == 0 {
+        bail!("port_block_size must be at least 1");
+    }
+    
+    let mut taken = ports_in_other_repositories(repo);
+    let mut allocated = None;
+    Metadata::update(repo, |metadata| {
+        taken.extend(metadata.worktrees.iter()
+            .filter(|(name, _)| name.as_str() != worktree_name)
+            .filter_map(|(_, entry)| entry.ports));
+        
+        let Some(entry) = metadata.worktrees.get_mut(worktree_name) else {
+            return;
+        };
+        if entry.ports.is_some() {
+            allocated = entry.ports;
+            return;
+        }
+        
+        let mut start = u32::from(config.port_base);
+        let count = u32::from(config.port_block_size);
+        while start + count - 1 <= u32::from(u16::MAX) {
+            let block = PortBlock { start: start as u16, count: count as u16 };
+            match taken.iter().filter(|other| other.overlaps(&block)).map(|other| u32::from(other.end())).max() {
+                Some(end) => start = end + 1,
+                None => {
+                    entry.ports = Some(block);
+                    allocated = Some(block);
+                    return;
+                }
+            }
+        }
+    })?;
+    
+    allocated.with_context(|| format!("No free block of {} ports left above {} for worktree '{}'",
+                                      config.port_block_size, config.port_base, worktree_name))
+}
+
+/// Environment variables describing a worktree and its ports
+///
+/// Worktrees created before ports were allocated, or by `git worktree add`,
+/// get a block the first time this is called.
+pub fn worktree_env(repo: &Repository, worktree_name: &str) -> Result<Vec<(String, String)>> {
+    let path = resolve_worktree_path(repo, worktree_name)?;
+    if !path.exists() {
+        bail!("Worktree '{}' not found at {:?}", worktree_name, path);
+    }
+    
+    worktree_env_at(repo, worktree_name, &path)
+}
+
+/// Like [`worktree_env`], for a worktree whose path the caller has already found
+pub(crate) fn worktree_env_at(repo: &Repository, worktree_name: &str, path: &Path) -> Result<Vec<(String, String)>> {
+    if !Metadata::load(repo)?.worktrees.contains_key(worktree_name) {
+        let branch = branch_of_worktree(repo, path)?;
+        Metadata::update(repo, |metadata| {
+            metadata.worktrees.insert(worktree_name.to_string(), WorktreeMetadata {
+                path: path.to_path_buf(),
+                branch,
+                ..Default::default()
+            });
+        })?;
+    }
+    let ports = allocate_ports(repo, worktree_name)?;
+    let branch = Metadata::load(repo)?.worktrees.remove(worktree_name).and_then(|entry| entry.branch);
+    
+    let mut env = vec![
+        ("GWTR_REPO".to_string(), get_repository_name(repo)?),
+        ("GWTR_WORKTREE".to_string(), worktree_name.to_string()),
+        ("GWTR_WORKTREE_PATH".to_string(), path.display().to_string()),
+    ];
+    if let Some(branch) = branch {
+        env.push(("GWTR_BRANCH".to_string(), branch));
+    }
+    env.push(("PORT".to_string(), ports.start.to_string()));
+    env.push(("GWTR_PORT".to_string(), ports.start.to_string()));
+    env.push(("GWTR_PORT_END".to_string(), ports.end().to_string()));
+    for offset in 0..ports.count {
+        env.push((format!("GWTR_PORT_{}", offset), (ports.start + offset).to_string()));
+    }
+    
+    Ok(env)
+}
+
+/// Quote a value for both POSIX shells and dotenv parsers if it needs it
+pub(crate) fn quote(value: &str) -> String {
+    let plain = value.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:@%+,".contains(c));
+    if plain && !value.is_empty() {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn format_env(env: &[(String, String)]) -> String {
+    env.iter()
+        .map(|(key, value)| format!("{}={}\n", key, quote(value)))
+        .collect()
+}
+
+/// Print the environment of a worktree as `KEY=value` lines, and with `write`
+/// also write it to the configured env file
+pub fn show_env(repo: &Repository, worktree_name: &str, write: bool) -> Result<()> {
+    let env = worktree_env(repo, worktree_name)?;
+    print!("{}", format_env(&env));
+    
+    if write {
+        let config = Config::load(repo)?;
+        let file = config.env_file.as_deref().unwrap_or(".env");
+        let path = resolve_worktree_path(repo, worktree_name)?;
+        write_env_file(repo, &path, file, &env)?;
+        eprintln!("Wrote {}", path.join(file).display());
+    }
+    
+    Ok(())
+}
+
+/// Write the environment into `file` inside the worktree at `worktree_path`
+///
+/// Only the block between gwtr's marker lines is replaced, so other settings in
+/// the file survive. The file is added to `info/exclude` so it never counts as
+/// uncommitted work; a file that git tracks is left alone.
+pub fn write_env_file(repo: &Repository, worktree_path: &Path, file: &str, env: &[(String, String)]) -> Result<()> {
+    let tracked = std::process::Command::new("git")
+        .args(["ls-files", "--error-unmatch", "--", file])
+        .current_dir(worktree_path)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if tracked {
+        bail!("Not writing {} in {:?}: the file is tracked by git", file, worktree_path);
+    }
+    
+    let path = worktree_path.join(file);
+    let existing = if path.exists() {
+        std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?
+    } else {
+        String::new()
+    };
+    
+    let block = format!("{}\n{}{}\n", ENV_BEGIN, format_env(env), ENV_END);
+    let content = match (existing.find(ENV_BEGIN), existing.find(ENV_END)) {
+        (Some(begin), Some(end)) if begin < end => {
+            let rest = existing[end + ENV_END.len()..].trim_start_matches('\n');
+            format!("{}{}{}", &existing[..begin], block, rest)
+        }
+        _ if existing.is_empty() => block,
+        _ if existing.ends_with('\n') => format!("{}{}", existing, block),
+        _ => format!("{}\n{}", existing, block),
+    };
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    
+    exclude_path(repo, file)
+}
+
+/// Add `/<file>` to the repository's `info/exclude` unless it is already there
+fn exclude_path(repo: &Repository, file: &str) -> Result<()> {
+    let exclude = get_common_dir(repo).join("info").join("exclude");
+    let pattern = format!("/{}", file.trim_start_matches('/'));
+    
+    let existing = std::fs::read_to_string(&exclude).unwrap_or_default();
+    if existing.lines().any(|line| line == pattern) {
+        return Ok(());
+    }
+    
+    if let Some(dir) = exclude.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+    std::fs::write(&exclude, format!("{}{}{}\n", existing, separator, pattern))
+        .with_context(|| format!("Failed to write {:?}", exclude))
+}
+
+/// Give a newly created worktree its port block and, if configured, its env file
+///
+/// Failures only warn: the worktree itself was created fine.
+pub(crate) fn set_up_worktree_env(repo: &Repository, worktree_name: &str, worktree_path: &Path) {
+    let result = allocate_ports(repo, worktree_name).and_then(|ports| {
+        println!("Ports {}-{} (see `gwtr env {}`)", ports.start, ports.end(), worktree_name);
+        
+        if let Some(file) = Config::load(repo)?.env_file {
+            write_env_file(repo, worktree_path, &file, &worktree_env_at(repo, worktree_name, worktree_path)?)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("{}: {:#}", "Warning".yellow(), e);
+    }
+}
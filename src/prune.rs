@@ -0,0 +1,544 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    Config, DirtyReport, JournalEntry, MergeRule, Metadata, Operation, TRASH_REF_PREFIX, branch_exists, branch_label,
+    delete_branch, detached_at_created_ref, detect_merge, dirty_report, format_duration, get_command_dir, get_worktrees,
+    head_commit, is_main_worktree, record_operation, refs_containing, save_to_trash, worktree_name,
+};
+use crate::temporary::delete_temporary_branch;
+use crate::{forget_worktree, kill_tmux_session, remove_worktree_directory};
+
+/// Options controlling which worktrees `prune_merged_worktrees` selects and how
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Show what would be pruned without removing anything
+    pub dry_run: bool,
+    /// Skip the confirmation prompt
+    pub force: bool,
+    /// Delete branches with `delete_branch` even if they are not merged into the base branch
+    pub delete_unmerged: bool,
+    /// Fetch with pruning and also select branches whose upstream was deleted
+    pub gone: bool,
+    /// Also select worktrees whose last commit is older than this
+    pub older_than: Option<Duration>,
+    /// Also select worktrees with no file modified within this period
+    pub inactive_for: Option<Duration>,
+    /// Select worktrees even if they have uncommitted changes
+    pub include_dirty: bool,
+    /// Select worktrees even if they are locked
+    pub include_locked: bool,
+    /// Also select temporary worktrees whose time to live has passed
+    pub expired: bool,
+    /// Delete the branch of each pruned worktree; `None` uses the configured default
+    pub delete_branch: Option<bool>,
+    /// Also delete the pruned branches on their remote; `None` uses the configured default
+    pub delete_remote_branch: Option<bool>,
+}
+
+/// Why a worktree was selected for pruning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneReason {
+    /// The branch was merged into the base branch
+    Merged(MergeRule),
+    /// The branch tracks a remote branch that no longer exists
+    UpstreamGone(String),
+    /// The worktree has a detached HEAD that moved off the ref it was created at
+    /// to a commit on the base branch, so removing it loses no commits
+    Detached(String),
+    /// The worktree was created by `gwtr tmp` and expired this long ago
+    Expired(Duration),
+    /// The worktree matched every requested age criterion
+    Stale {
+        last_commit: Option<Duration>,
+        inactive: Option<Duration>,
+    },
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneReason::Merged(rule) => write!(f, "{}", rule),
+            PruneReason::UpstreamGone(upstream) => write!(f, "upstream {} gone", upstream),
+            PruneReason::Detached(reference) => write!(f, "detached HEAD contained in {}", reference),
+            PruneReason::Expired(age) => write!(f, "temporary, expired {} ago", format_duration(*age)),
+            PruneReason::Stale { last_commit, inactive } => {
+                let mut parts = Vec::new();
+                if let Some(age) = last_commit {
+                    parts.push(format!("last commit {} ago", format_duration(*age)));
+                }
+                if let Some(age) = inactive {
+                    parts.push(format!("inactive for {}", format_duration(*age)));
+                }
+                write!(f, "{}", parts.join(", "))
+            }
+        }
+    }
+}
+
+struct PruneCandidate {
+    path: String,
+    /// Branch checked out, `None` for a detached HEAD
+    branch: Option<String>,
+    /// Branch name, or a description of the detached HEAD
+    label: String,
+    name: String,
+    reason: PruneReason,
+    /// Uncommitted changes, for dirty worktrees selected with `include_dirty`
+    dirty: Option<DirtyReport>,
+    /// Whether the worktree is locked and has to be unlocked before removal
+    locked: bool,
+}
+
+/// Fetch all remotes, removing remote-tracking refs whose branch was deleted
+fn fetch_with_prune(workdir: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .args(["fetch", "--all", "--prune"])
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git fetch command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to fetch: {}", stderr.trim());
+    }
+    
+    Ok(())
+}
+
+/// Local branches whose configured upstream no longer exists, with that upstream
+fn branches_with_gone_upstream(workdir: &Path) -> Result<Vec<(String, String)>> {
+    let output = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname:short)%00%(upstream:short)%00%(upstream:track)", "refs/heads"])
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git for-each-ref command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to list branches: {}", stderr.trim());
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let branch = fields.next()?;
+            let upstream = fields.next()?;
+            let track = fields.next()?;
+            (track == "[gone]").then(|| (branch.to_string(), upstream.to_string()))
+        })
+        .collect())
+}
+
+fn print_candidate(candidate: &PruneCandidate) {
+    println!("  {} [{}] at {} ({})", candidate.name.yellow(), candidate.label.cyan(), candidate.path, candidate.reason);
+    if let Some(report) = &candidate.dirty {
+        println!("    {}", "uncommitted changes that would be lost:".red());
+        report.print();
+    }
+}
+
+/// Whether `commit` is reachable from `base_branch`
+fn is_on_base(workdir: &Path, commit: &str, base_branch: &str) -> Result<bool> {
+    let output = Command::new("git")
+        .args(["merge-base", "--is-ancestor", commit, &format!("refs/heads/{}", base_branch)])
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git merge-base command")?;
+    
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("Failed to check whether {} is on '{}': {}", commit, base_branch, stderr.trim());
+        }
+    }
+}
+
+/// Time elapsed since the last commit on `revision`, a branch or a commit
+fn last_commit_age(workdir: &Path, revision: &str) -> Result<Duration> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%ct", revision])
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git log command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to read last commit of '{}': {}", revision, stderr.trim());
+    }
+    
+    let timestamp: u64 = String::from_utf8_lossy(&output.stdout).trim().parse()
+        .with_context(|| format!("Failed to parse commit date of '{}'", revision))?;
+    let committed_at = SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp);
+    
+    Ok(SystemTime::now().duration_since(committed_at).unwrap_or_default())
+}
+
+/// Time elapsed since any tracked or untracked (but not ignored) file was modified
+fn inactivity(worktree_path: &Path) -> Result<Duration> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z", "--cached", "--others", "--exclude-standard"])
+        .current_dir(worktree_path)
+        .output()
+        .context("Failed to execute git ls-files command")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to list files in {:?}: {}", worktree_path, stderr.trim());
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last_modified = stdout
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .filter_map(|file| std::fs::symlink_metadata(worktree_path.join(file)).ok())
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    
+    Ok(SystemTime::now().duration_since(last_modified).unwrap_or_default())
+}
+
+/// Check the requested age criteria, returning a reason only if all of them match
+fn stale_reason(workdir: &Path, worktree_path: &Path, revision: &str, options: &PruneOptions) -> Result<Option<PruneReason>> {
+    if options.older_than.is_none() && options.inactive_for.is_none() {
+        return Ok(None);
+    }
+    
+    let mut last_commit = None;
+    if let Some(threshold) = options.older_than {
+        let age = last_commit_age(workdir, revision)?;
+        if age < threshold {
+            return Ok(None);
+        }
+        last_commit = Some(age);
+    }
+    
+    let mut inactive = None;
+    if let Some(threshold) = options.inactive_for {
+        let age = inactivity(worktree_path)?;
+        if age < threshold {
+            return Ok(None);
+        }
+        inactive = Some(age);
+    }
+    
+    Ok(Some(PruneReason::Stale { last_commit, inactive }))
+}
+
+/// Prune merged worktrees
+///
+/// A worktree is pruned when its branch was merged into the base branch by any
+/// of the rules in [`MergeRule`], so squash and rebase merges are recognised too.
+/// With `gone`, worktrees whose upstream branch was deleted are selected as well,
+/// and with `older_than`/`inactive_for` so are worktrees matching every age limit.
+/// Worktrees with uncommitted changes are skipped unless `include_dirty` is set.
+pub fn prune_merged_worktrees(repo: &Repository, options: &PruneOptions) -> Result<()> {
+    let config = Config::load(repo)?;
+    let workdir = get_command_dir(repo);
+    let base_branch = config.base_branch.as_str();
+    let delete_branches = options.delete_branch.unwrap_or(config.delete_branch);
+    let delete_remote = options.delete_remote_branch.unwrap_or(config.delete_remote_branch);
+    
+    let gone_branches = if options.gone {
+        println!("Fetching with --prune...");
+        fetch_with_prune(workdir)?;
+        branches_with_gone_upstream(workdir)?
+    } else {
+        Vec::new()
+    };
+    
+    let worktrees = get_worktrees(repo)?;
+    let metadata = Metadata::load(repo)?;
+    let mut candidates = Vec::new();
+    
+    let base_exists = branch_exists(workdir, base_branch);
+    if !base_exists {
+        eprintln!("{}: base branch '{}' not found", "Warning".yellow(), base_branch);
+    }
+    
+    for worktree in &worktrees {
+        let path = worktree.path.to_string_lossy().to_string();
+        
+        // Skip main worktree and the repository of a bare layout
+        if worktree.is_bare || is_main_worktree(repo, &worktree.path) {
+            continue;
+        }
+        
+        // The base branch is never pruned, even where it is checked out in a linked worktree
+        if worktree.branch.as_deref() == Some(base_branch) {
+            continue;
+        }
+        let branch = worktree.branch.as_deref();
+        let label = branch_label(&metadata, worktree);
+        
+        if let Some(reason) = &worktree.locked
+            && !options.include_locked {
+            let reason = if reason.is_empty() { String::new() } else { format!(": {}", reason) };
+            println!("Skipping {} [{}]: locked{} (use --include-locked to prune it)",
+                     path.yellow(), label.cyan(), reason);
+            continue;
+        }
+        
+        let expired = metadata.entry_for_path(&worktree.path)
+            .and_then(|(_, entry)| entry.temporary?.expired_for())
+            .filter(|_| options.expired)
+            .map(PruneReason::Expired);
+        
+        let reason = match branch {
+            _ if expired.is_some() => expired,
+            Some(branch) => {
+                let merged = if base_exists {
+                    match detect_merge(workdir, branch, base_branch) {
+                        Ok(rule) => rule.map(PruneReason::Merged),
+                        Err(e) => {
+                            eprintln!("{}: skipping '{}': {}", "Warning".yellow(), branch, e);
+                            continue;
+                        }
+                    }
+                } else {
+                    None
+                };
+                
+                let gone = gone_branches
+                    .iter()
+                    .find(|(gone_branch, _)| gone_branch == branch)
+                    .map(|(_, upstream)| PruneReason::UpstreamGone(upstream.clone()));
+                
+                let stale = if merged.is_none() && gone.is_none() {
+                    match stale_reason(workdir, &worktree.path, branch, options) {
+                        Ok(reason) => reason,
+                        Err(e) => {
+                            eprintln!("{}: skipping '{}': {}", "Warning".yellow(), branch, e);
+                            continue;
+                        }
+                    }
+                } else {
+                    None
+                };
+                
+                merged.or(gone).or(stale)
+            }
+            None => {
+                let Some(head) = worktree.head.as_deref() else {
+                    continue;
+                };
+                
+                // A worktree still at the tag or commit it was created to look at is
+                // only pruned by age or expiry, however old that commit is
+                let on_base = if base_exists && detached_at_created_ref(&metadata, worktree).is_none() {
+                    match is_on_base(workdir, head, base_branch) {
+                        Ok(on_base) => on_base,
+                        Err(e) => {
+                            eprintln!("{}: skipping {}: {}", "Warning".yellow(), path, e);
+                            continue;
+                        }
+                    }
+                } else {
+                    false
+                };
+                
+                let reason = if on_base {
+                    Some(PruneReason::Detached(base_branch.to_string()))
+                } else {
+                    match stale_reason(workdir, &worktree.path, head, options) {
+                        Ok(reason) => reason,
+                        Err(e) => {
+                            eprintln!("{}: skipping {}: {}", "Warning".yellow(), path, e);
+                            continue;
+                        }
+                    }
+                };
+                
+                // Commits made on a detached HEAD are only kept alive by the worktree
+                if reason.is_some() && !on_base {
+                    match refs_containing(workdir, head) {
+                        Ok(refs) if !refs.is_empty() => {}
+                        Ok(_) => {
+                            println!("Skipping {} [{}]: its commits are on no branch or tag and would be lost (create a branch to keep them)",
+                                     path.yellow(), label.cyan());
+                            continue;
+                        }
+                        Err(e) => {
+                            eprintln!("{}: skipping {}: {}", "Warning".yellow(), path, e);
+                            continue;
+                        }
+                    }
+                }
+                reason
+            }
+        };
+        
+        let Some(reason) = reason else {
+            continue;
+        };
+        
+        // Refuse before touching the worktree if its branch could not be deleted afterwards
+        if delete_branches && !options.delete_unmerged
+            && let Some(branch) = branch
+            && !matches!(reason, PruneReason::Merged(_))
+            && detect_merge(workdir, branch, base_branch).ok().flatten().is_none() {
+            println!("Skipping {} [{}]: branch is not merged into '{}' (use --delete-unmerged to delete it anyway, or --keep-branch)",
+                     path.yellow(), label.cyan(), base_branch);
+            continue;
+        }
+        
+        let report = match dirty_report(&worktree.path) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("{}: skipping '{}': {}", "Warning".yellow(), label, e);
+                continue;
+            }
+        };
+        
+        if report.is_dirty() && !options.include_dirty {
+            println!("Skipping {} [{}]: {} uncommitted changes (use --include-dirty to prune it)",
+                     path.yellow(), label.cyan(), report.changed.len() + report.untracked.len());
+            continue;
+        }
+        
+        let worktree_name = worktree_name(repo, &worktree.path).unwrap_or_else(|_| label.clone());
+        
+        candidates.push(PruneCandidate {
+            path,
+            branch: branch.map(str::to_string),
+            label,
+            name: worktree_name,
+            reason,
+            dirty: report.is_dirty().then_some(report),
+            locked: worktree.locked.is_some(),
+        });
+    }
+    
+    if candidates.is_empty() {
+        println!("No worktrees to prune");
+        return Ok(());
+    }
+    
+    if options.dry_run {
+        println!("Would prune {} worktree{}:", 
+                 candidates.len(), 
+                 if candidates.len() == 1 { "" } else { "s" });
+        for candidate in &candidates {
+            print_candidate(candidate);
+        }
+        return Ok(());
+    }
+    
+    // Show worktrees to be pruned
+    println!("Found {} worktree{} to prune:", 
+             candidates.len(), 
+             if candidates.len() == 1 { "" } else { "s" });
+    for candidate in &candidates {
+        print_candidate(candidate);
+    }
+    
+    // Ask for confirmation unless --force is used
+    if !options.force {
+        print!("\nPrune these worktrees? [y/N] ");
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+    
+    // Prune each worktree
+    let total = candidates.len();
+    let mut pruned_count = 0;
+    for candidate in candidates {
+        print!("Pruning {}... ", candidate.name.yellow());
+        
+        let mut journal_entry = JournalEntry::new(Operation::Prune, &candidate.name);
+        journal_entry.path = Some(candidate.path.clone().into());
+        journal_entry.branch = candidate.branch.clone();
+        journal_entry.before = head_commit(Path::new(&candidate.path));
+        
+        // Keep a recoverable copy of uncommitted work before deleting it
+        if candidate.dirty.is_some() {
+            match save_to_trash(Path::new(&candidate.path), &candidate.name, candidate.branch.as_deref()) {
+                Ok(entry) => {
+                    print!("saved changes to {}{}... ", TRASH_REF_PREFIX, entry.name);
+                    journal_entry.trash = Some(entry.name);
+                }
+                Err(e) => {
+                    println!("{}", "failed".red());
+                    eprintln!("  Error: could not save uncommitted changes, not removing: {}", e);
+                    continue;
+                }
+            }
+        }
+        
+        if candidate.locked {
+            let output = Command::new("git")
+                .args(["worktree", "unlock", &candidate.path])
+                .current_dir(workdir)
+                .output()
+                .context("Failed to execute git worktree unlock command")?;
+            
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                println!("{}", "failed".red());
+                eprintln!("  Error: {}", stderr.trim());
+                continue;
+            }
+        }
+        
+        // Only worktrees confirmed as dirty above lose their changes
+        if let Err(e) = remove_worktree_directory(workdir, Path::new(&candidate.path), candidate.dirty.is_some()) {
+            println!("{}", "failed".red());
+            eprintln!("  Error: {}", e);
+            continue;
+        }
+        println!("{}", "done".green());
+        pruned_count += 1;
+        journal_entry.metadata = forget_worktree(repo, &candidate.name);
+        kill_tmux_session(repo, &candidate.name);
+        
+        if delete_branches
+            && let Some(branch) = &candidate.branch {
+            match delete_branch(workdir, branch, base_branch, options.delete_unmerged, delete_remote) {
+                Ok(()) => journal_entry.branch_deleted = true,
+                Err(e) => eprintln!("  Error: {}", e),
+            }
+        } else if matches!(candidate.reason, PruneReason::Expired(_))
+            && let Some(branch) = &candidate.branch {
+            // Temporary branches go with their worktree unless they hold the only copy of commits
+            match delete_temporary_branch(workdir, branch) {
+                Ok(true) => {
+                    println!("Deleted branch '{}'", branch.cyan());
+                    journal_entry.branch_deleted = true;
+                }
+                Ok(false) => println!("Kept branch '{}': it has commits that are on no other branch", branch.cyan()),
+                Err(e) => eprintln!("  Error: {}", e),
+            }
+        }
+        
+        record_operation(repo, journal_entry);
+    }
+    
+    println!("\nPruned {} worktree{}", 
+             pruned_count, 
+             if pruned_count == 1 { "" } else { "s" });
+    
+    let failed = total - pruned_count;
+    if failed > 0 {
+        bail!("Failed to prune {} worktree{}", failed, if failed == 1 { "" } else { "s" });
+    }
+    
+    Ok(())
+}
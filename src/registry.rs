@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use colored::*;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use crate::{get_project_root, user_config_dir};
+
+/// Repositories gwtr has operated on, stored in the user-level configuration directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registry {
+    /// Main worktree, or project directory of a bare layout, of each repository
+    #[serde(default)]
+    pub repositories: BTreeSet<PathBuf>,
+}
+
+/// Location of the registry, or `None` if there is no home directory
+pub fn registry_path() -> Option<PathBuf> {
+    user_config_dir().map(|dir| dir.join("repositories.json"))
+}
+
+impl Registry {
+    /// Load the registry, or an empty one if none was saved yet
+    pub fn load() -> Result<Registry> {
+        let Some(path) = registry_path().filter(|path| path.exists()) else {
+            return Ok(Registry::default());
+        };
+        
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?}", path))
+    }
+    
+    /// Save the registry atomically, so concurrent invocations never see a partial file
+    pub fn save(&self) -> Result<()> {
+        let path = registry_path().context("No home directory to store the registry in")?;
+        let dir = path.parent().context("Registry path has no parent directory")?;
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {:?}", dir))?;
+        
+        let temp_path = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {:?}", temp_path))?;
+        std::fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to replace {:?}", path))
+    }
+}
+
+/// Remember the repository for the `--global` commands
+///
+/// Failures are ignored: the registry is a convenience and must not get in the
+/// way of the command being run.
+pub fn register_repository(repo: &Repository) {
+    let root = get_project_root(repo);
+    let root = root.canonicalize().unwrap_or(root);
+    
+    if let Ok(mut registry) = Registry::load()
+        && registry.repositories.insert(root) {
+        let _ = registry.save();
+    }
+}
+
+/// Open every registered repository, forgetting those that no longer exist
+pub fn registered_repositories() -> Result<Vec<(PathBuf, Repository)>> {
+    let mut registry = Registry::load()?;
+    let mut repositories = Vec::new();
+    let mut vanished = Vec::new();
+    
+    for root in &registry.repositories {
+        match Repository::open(root) {
+            Ok(repo) => repositories.push((root.clone(), repo)),
+            Err(_) => vanished.push(root.clone()),
+        }
+    }
+    
+    if !vanished.is_empty() {
+        for root in &vanished {
+            println!("Forgetting {} (no longer a repository)", root.display().to_string().dimmed());
+            registry.repositories.remove(root);
+        }
+        registry.save()?;
+    }
+    
+    Ok(repositories)
+}
+
+/// Run `f` for every registered repository under a heading naming it
+///
+/// An error in one repository is reported and does not stop the others.
+pub fn for_each_repository<F: FnMut(&Repository) -> Result<()>>(mut f: F) -> Result<()> {
+    let repositories = registered_repositories()?;
+    if repositories.is_empty() {
+        println!("No repositories registered yet; run gwtr inside a repository first");
+        return Ok(());
+    }
+    
+    for (index, (root, repo)) in repositories.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("{}", format!("{}:", root.display()).bold());
+        if let Err(e) = f(repo) {
+            eprintln!("{}: {}: {:#}", "Error".red(), root.display(), e);
+        }
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    JournalEntry, Metadata, Operation, branch_of_worktree, canonical, get_command_dir, get_main_worktree,
+    get_worktree_path, git, is_main_worktree, record_operation, resolve_worktree_path,
+};
+use crate::submodule::{has_initialized_submodules, move_worktree_with_submodules};
+
+/// Run the rollback steps of a failed rename, newest first, reporting any that fail
+fn roll_back(workdir: &Path, steps: &[Vec<String>]) {
+    for step in steps.iter().rev() {
+        let args: Vec<&str> = step.iter().map(String::as_str).collect();
+        if let Err(e) = git(workdir, &args) {
+            eprintln!("{}: rollback failed, fix manually: {}", "Warning".yellow(), e);
+        }
+    }
+}
+
+/// Move a worktree directory with `git worktree move`, which cannot move
+/// worktrees with checked-out submodules, so those are moved by hand
+fn move_directory(workdir: &Path, from: &Path, to: &Path) -> Result<()> {
+    if has_initialized_submodules(from) {
+        return move_worktree_with_submodules(workdir, from, to);
+    }
+    git(workdir, &["worktree", "move", &from.to_string_lossy(), &to.to_string_lossy()])?;
+    Ok(())
+}
+
+/// Move a worktree directory and optionally rename its branch, updating the
+/// stored metadata; every completed step is rolled back if a later one fails
+pub(crate) fn move_worktree(
+    repo: &Repository,
+    old_name: &str,
+    old_path: &Path,
+    new_name: &str,
+    new_path: &Path,
+    branch_rename: Option<(&str, &str)>,
+) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    let mut rollback: Vec<Vec<String>> = Vec::new();
+    
+    // Never take over the name of another registered worktree
+    if let Some(entry) = Metadata::load(repo)?.worktrees.get(new_name)
+        && canonical(&entry.path) != canonical(old_path) {
+        bail!("The name '{}' is already used by the worktree at {:?}", new_name, entry.path);
+    }
+    
+    let moved = old_path != new_path;
+    if moved {
+        move_directory(workdir, old_path, new_path).context("Failed to move worktree")?;
+    }
+    let roll_back = |steps: &[Vec<String>]| {
+        roll_back(workdir, steps);
+        if moved && let Err(e) = move_directory(workdir, new_path, old_path) {
+            eprintln!("{}: rollback failed, fix manually: {}", "Warning".yellow(), e);
+        }
+    };
+    
+    if let Some((old_branch, new_branch)) = branch_rename {
+        // `git branch -m` carries the branch's upstream configuration along
+        if let Err(e) = git(workdir, &["branch", "-m", old_branch, new_branch]) {
+            roll_back(&rollback);
+            return Err(e.context("Failed to rename branch"));
+        }
+        rollback.push(vec!["branch".into(), "-m".into(), new_branch.into(), old_branch.into()]);
+    }
+    
+    let result = Metadata::update(repo, |metadata| {
+        let mut entry = metadata.worktrees.remove(old_name).unwrap_or_default();
+        entry.path = new_path.to_path_buf();
+        if let Some((_, new_branch)) = branch_rename {
+            entry.branch = Some(new_branch.to_string());
+        }
+        metadata.worktrees.insert(new_name.to_string(), entry);
+    });
+    
+    if let Err(e) = result {
+        roll_back(&rollback);
+        return Err(e.context("Failed to update worktree metadata"));
+    }
+    
+    Ok(())
+}
+
+/// Rename a worktree, moving it to the path the layout template computes for
+/// the new name and optionally renaming its branch as well
+pub fn rename_worktree(repo: &Repository, old_name: &str, new_name: &str, rename_branch: bool) -> Result<PathBuf> {
+    let old_path = resolve_worktree_path(repo, old_name)?;
+    if is_main_worktree(repo, &old_path) || (old_name == "main" && get_main_worktree(repo).is_some()) {
+        bail!("The main worktree cannot be renamed");
+    }
+    let new_path = get_worktree_path(repo, new_name)?;
+    
+    if !old_path.exists() {
+        bail!("Worktree '{}' not found at {:?}", old_name, old_path);
+    }
+    if new_path.exists() {
+        bail!("Cannot rename to '{}': {:?} already exists", new_name, new_path);
+    }
+    
+    let branch = branch_of_worktree(repo, &old_path)?;
+    let branch_rename = match (&branch, rename_branch) {
+        (Some(branch), true) => Some((branch.as_str(), new_name)),
+        (None, true) => bail!("Worktree '{}' has no branch checked out to rename", old_name),
+        (_, false) => None,
+    };
+    
+    move_worktree(repo, old_name, &old_path, new_name, &new_path, branch_rename)?;
+    
+    println!("Renamed worktree '{}' to '{}' at {:?}", old_name, new_name, new_path);
+    if let Some((old_branch, new_branch)) = branch_rename {
+        println!("Renamed branch '{}' to '{}'", old_branch.cyan(), new_branch.cyan());
+    }
+    println!("cd '{}'", new_path.display());
+    
+    let mut entry = JournalEntry::new(Operation::Rename, new_name);
+    entry.path = Some(new_path.clone());
+    entry.branch = branch_rename.map(|(_, new_branch)| new_branch.to_string()).or(branch.clone());
+    entry.renamed_from = Some(old_name.to_string());
+    entry.previous_path = Some(old_path);
+    entry.previous_branch = branch_rename.map(|(old_branch, _)| old_branch.to_string());
+    record_operation(repo, entry);
+    
+    Ok(new_path)
+}
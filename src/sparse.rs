@@ -0,0 +1,103 @@
+use anyhow::{Result, bail};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{Config, Metadata, branch_of_worktree, git, resolve_worktree_path};
+
+/// How `gwtr sparse` changes the directories of a worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SparseAction {
+    /// Check out these directories as well
+    Add,
+    /// Check out only these directories
+    Set,
+}
+
+/// The sparse checkout of a worktree, as recorded in its metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseCheckout {
+    /// Profile the worktree was created with, until `gwtr sparse set` replaces it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Directories checked out in cone mode
+    pub directories: Vec<String>,
+}
+
+impl SparseCheckout {
+    /// `profile (dir, dir)`, or just the directories without a profile
+    pub fn describe(&self) -> String {
+        let directories = self.directories.join(", ");
+        match &self.profile {
+            Some(profile) => format!("{} ({})", profile, directories),
+            None => directories,
+        }
+    }
+}
+
+/// The directories of a configured sparse profile
+pub fn sparse_profile(config: &Config, profile: &str) -> Result<Vec<String>> {
+    match config.sparse_profiles.get(profile) {
+        Some(directories) if !directories.is_empty() => Ok(directories.clone()),
+        Some(_) => bail!("Sparse profile '{}' has no directories", profile),
+        None if config.sparse_profiles.is_empty() => {
+            bail!("Unknown sparse profile '{}'; define profiles under [sparse_profiles] in .gwtr.toml", profile)
+        }
+        None => {
+            let known: Vec<&str> = config.sparse_profiles.keys().map(String::as_str).collect();
+            bail!("Unknown sparse profile '{}'; configured profiles: {}", profile, known.join(", "))
+        }
+    }
+}
+
+/// Restrict a worktree created with `--no-checkout` to `directories` and check it out
+pub(crate) fn check_out_sparse(worktree_path: &Path, directories: &[String]) -> Result<()> {
+    let mut args = vec!["sparse-checkout", "set", "--cone"];
+    args.extend(directories.iter().map(String::as_str));
+    git(worktree_path, &args)?;
+    git(worktree_path, &["checkout"])?;
+    Ok(())
+}
+
+/// Add directories to, or replace the directories of, a worktree's sparse checkout
+///
+/// A worktree that is not sparse yet becomes sparse with only the given directories.
+pub fn sparse_worktree(repo: &Repository, worktree_name: &str, action: SparseAction, directories: &[String]) -> Result<()> {
+    let path = resolve_worktree_path(repo, worktree_name)?;
+    if !path.exists() {
+        bail!("Worktree '{}' not found at {:?}", worktree_name, path);
+    }
+    
+    let is_sparse = git(&path, &["config", "--bool", "core.sparseCheckout"]).is_ok_and(|value| value == "true");
+    let subcommand = match action {
+        SparseAction::Add if is_sparse => "add",
+        _ => "set",
+    };
+    let mut args = vec!["sparse-checkout", subcommand];
+    if subcommand == "set" {
+        args.push("--cone");
+    }
+    args.extend(directories.iter().map(String::as_str));
+    git(&path, &args)?;
+    
+    let current: Vec<String> = git(&path, &["sparse-checkout", "list"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let branch = branch_of_worktree(repo, &path)?;
+    Metadata::update(repo, |metadata| {
+        let entry = metadata.worktrees.entry(worktree_name.to_string()).or_default();
+        if entry.path.as_os_str().is_empty() {
+            entry.path = path.clone();
+            entry.branch = branch;
+        }
+        let profile = match action {
+            SparseAction::Add => entry.sparse.as_ref().and_then(|sparse| sparse.profile.clone()),
+            SparseAction::Set => None,
+        };
+        entry.sparse = Some(SparseCheckout { profile, directories: current.clone() });
+    })?;
+    
+    println!("Sparse checkout of '{}': {}", worktree_name, current.join(", "));
+    Ok(())
+}
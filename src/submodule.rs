@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use colored::*;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+use crate::{Trim, get_common_dir, git, run_git};
+
+/// Names and paths of the submodules declared in the worktree's `.gitmodules`
+fn declared_submodules(worktree_path: &Path) -> Result<Vec<(String, String)>> {
+    if !worktree_path.join(".gitmodules").exists() {
+        return Ok(Vec::new());
+    }
+    
+    // Exits with 1 when no submodule has a path
+    let output = git(worktree_path, &["config", "--file", ".gitmodules", "--get-regexp", r"^submodule\..*\.path$"])
+        .unwrap_or_default();
+    Ok(output.lines()
+        .filter_map(|line| {
+            let (key, path) = line.split_once(' ')?;
+            let name = key.strip_prefix("submodule.")?.strip_suffix(".path")?;
+            Some((name.to_string(), path.to_string()))
+        })
+        .collect())
+}
+
+/// Whether any submodule of the worktree is checked out, which makes git refuse
+/// to remove the worktree without `--force` and to move it at all
+pub(crate) fn has_initialized_submodules(worktree_path: &Path) -> bool {
+    declared_submodules(worktree_path)
+        .unwrap_or_default()
+        .iter()
+        .any(|(_, path)| worktree_path.join(path).join(".git").exists())
+}
+
+/// Point the checked-out submodules of a moved worktree, and theirs in turn, back
+/// at their git directories inside `git_dir`, and those at the new checkouts
+fn relink_submodules(worktree_path: &Path, git_dir: &Path) -> Result<()> {
+    for (name, path) in declared_submodules(worktree_path)? {
+        let module_dir = git_dir.join("modules").join(&name);
+        let checkout = worktree_path.join(&path);
+        if !module_dir.is_dir() || !checkout.join(".git").exists() {
+            continue;
+        }
+        
+        std::fs::write(checkout.join(".git"), format!("gitdir: {}\n", module_dir.display()))
+            .with_context(|| format!("Failed to update {:?}", checkout.join(".git")))?;
+        git(worktree_path, &["config", "--file", &module_dir.join("config").to_string_lossy(),
+                             "core.worktree", &checkout.to_string_lossy()])?;
+        relink_submodules(&checkout, &module_dir)?;
+    }
+    
+    Ok(())
+}
+
+/// Move a worktree with checked-out submodules, which `git worktree move` refuses
+/// to do: move the directory, let `git worktree repair` reconnect it and fix the
+/// links between the submodules and their git directories
+pub(crate) fn move_worktree_with_submodules(workdir: &Path, old_path: &Path, new_path: &Path) -> Result<()> {
+    std::fs::rename(old_path, new_path)
+        .with_context(|| format!("Failed to move {:?} to {:?}", old_path, new_path))?;
+    
+    let relinked = git(workdir, &["worktree", "repair", &new_path.to_string_lossy()])
+        .and_then(|_| git(new_path, &["rev-parse", "--absolute-git-dir"]))
+        .and_then(|git_dir| relink_submodules(new_path, Path::new(&git_dir)));
+    if let Err(e) = relinked {
+        // Put the directory back where git still expects it
+        if std::fs::rename(new_path, old_path).is_ok() {
+            let _ = git(workdir, &["worktree", "repair", &old_path.to_string_lossy()]);
+        }
+        return Err(e);
+    }
+    
+    Ok(())
+}
+
+/// The main worktree's clone of the submodule, which a new clone can borrow objects from
+///
+/// Clones inside linked worktrees' git directories are never used: they are
+/// deleted with their worktree, which would break the borrowing clone.
+fn reference_for(repo: &Repository, name: &str) -> Option<PathBuf> {
+    let candidate = get_common_dir(repo).join("modules").join(name);
+    candidate.join("objects").is_dir().then_some(candidate)
+}
+
+/// Initialise and check out the submodules of a new worktree
+///
+/// Each submodule that is already cloned in the main worktree is cloned with
+/// `--reference` to that clone, so its objects are not fetched again.
+pub fn update_submodules(repo: &Repository, worktree_path: &Path) -> Result<()> {
+    let submodules = declared_submodules(worktree_path)?;
+    if submodules.is_empty() {
+        return Ok(());
+    }
+    
+    let mut referenced = 0;
+    for (name, path) in &submodules {
+        let mut args = vec!["submodule", "update", "--init", "--recursive"];
+        let reference = reference_for(repo, name).map(|reference| reference.to_string_lossy().to_string());
+        if let Some(reference) = &reference {
+            args.extend(["--reference", reference]);
+            referenced += 1;
+        }
+        args.extend(["--", path]);
+        
+        git(worktree_path, &args)
+            .with_context(|| format!("Failed to initialise submodule '{}'", name))?;
+    }
+    
+    println!("Initialised {} submodule(s), {} using objects from existing clones", submodules.len(), referenced);
+    Ok(())
+}
+
+/// State of the submodules of a worktree, by submodule path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubmoduleReport {
+    /// Declared but not checked out
+    pub uninitialized: Vec<String>,
+    /// Checked out at a different commit than the worktree records
+    pub drifted: Vec<String>,
+    /// With uncommitted changes or untracked files
+    pub dirty: Vec<String>,
+}
+
+impl SubmoduleReport {
+    pub fn is_empty(&self) -> bool {
+        self.uninitialized.is_empty() && self.drifted.is_empty() && self.dirty.is_empty()
+    }
+    
+    /// One-line summary such as `1 drifted (lib), 1 dirty (vendor/dep)`
+    pub fn summary(&self) -> String {
+        [
+            (&self.uninitialized, "uninitialized"),
+            (&self.drifted, "drifted"),
+            (&self.dirty, "dirty"),
+        ]
+            .iter()
+            .filter(|(paths, _)| !paths.is_empty())
+            .map(|(paths, label)| format!("{} {} ({})", paths.len(), label, paths.join(", ")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Check the submodules of the worktree at `path` for drift and uncommitted changes
+pub fn submodule_report(worktree_path: &Path) -> Result<SubmoduleReport> {
+    let mut report = SubmoduleReport::default();
+    if declared_submodules(worktree_path)?.is_empty() {
+        return Ok(report);
+    }
+    
+    // `-<sha> <path>` marks a submodule that is not initialised
+    // Leading whitespace is significant in `git submodule status`
+    for line in run_git(worktree_path, &["submodule", "status"], &[], Trim::End)?.lines() {
+        if let Some(rest) = line.strip_prefix('-')
+            && let Some((_, path)) = rest.split_once(' ') {
+            report.uninitialized.push(path.to_string());
+        }
+    }
+    
+    // Porcelain v2 describes submodules as `S<c><m><u>`: commit changed,
+    // tracked changes, untracked files
+    for line in git(worktree_path, &["status", "--porcelain=v2", "--ignore-submodules=none"])?.lines() {
+        let fields: Vec<&str> = line.splitn(9, ' ').collect();
+        if fields.len() < 9 || fields[0] != "1" {
+            continue;
+        }
+        let Some(flags) = fields[2].strip_prefix('S') else {
+            continue;
+        };
+        let path = fields[8].to_string();
+        if flags.starts_with('C') {
+            report.drifted.push(path.clone());
+        }
+        if flags[1..].contains(['M', 'U']) {
+            report.dirty.push(path);
+        }
+    }
+    
+    Ok(report)
+}
+
+/// Print a warning if initialising submodules failed; the worktree itself is fine
+pub(crate) fn update_submodules_or_warn(repo: &Repository, worktree_path: &Path) {
+    if let Err(e) = update_submodules(repo, worktree_path) {
+        eprintln!("{}: {:#}", "Warning".yellow(), e);
+        eprintln!("Run `git submodule update --init --recursive` in {:?} to retry", worktree_path);
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    AddOptions, Config, JournalEntry, Metadata, Operation, branch_exists, canonical, create_worktree_with,
+    discover_repository, dirty_report, format_duration, forget_worktree, get_command_dir, get_worktree_path,
+    get_worktrees, head_commit, parse_duration, record_operation, refs_containing, remove_worktree_directory,
+};
+use crate::tmux::kill_tmux_session_quietly;
+
+/// Marks a worktree created by `gwtr tmp`, which expires after its time to live
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Temporary {
+    /// Unix timestamp of when the worktree was created
+    pub created_at: u64,
+    /// Unix timestamp after which the worktree may be removed
+    pub expires_at: u64,
+}
+
+impl Temporary {
+    /// When the worktree expires, or `None` if that is too far away for the system clock
+    fn expiry(&self) -> Option<SystemTime> {
+        SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(self.expires_at))
+    }
+    
+    /// How long ago the worktree expired, or `None` if it has not yet
+    pub fn expired_for(&self) -> Option<Duration> {
+        SystemTime::now().duration_since(self.expiry()?).ok()
+    }
+    
+    /// `expires in 2d` or `expired 3h ago`
+    pub fn describe(&self) -> String {
+        match self.expired_for() {
+            Some(age) => format!("expired {} ago", format_duration(age)),
+            None => {
+                let left = self.expiry()
+                    .and_then(|expiry| expiry.duration_since(SystemTime::now()).ok())
+                    .unwrap_or(Duration::MAX);
+                format!("expires in {}", format_duration(left))
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The first `tmp-<n>` that is not used as a worktree name, branch or path
+fn next_temporary_name(repo: &Repository) -> Result<String> {
+    let workdir = get_command_dir(repo);
+    let metadata = Metadata::load(repo)?;
+    
+    for n in 1.. {
+        let name = format!("tmp-{}", n);
+        if !metadata.worktrees.contains_key(&name)
+            && !branch_exists(workdir, &name)
+            && !get_worktree_path(repo, &name)?.exists() {
+            return Ok(name);
+        }
+    }
+    unreachable!("ran out of temporary worktree names")
+}
+
+/// Create an auto-named temporary worktree that expires after `ttl`, or the
+/// configured `tmp_ttl`, on a new branch starting at `from` (the current HEAD by default)
+pub fn create_temporary_worktree(repo: &Repository, ttl: Option<Duration>, from: Option<&str>) -> Result<PathBuf> {
+    let ttl = match ttl {
+        Some(ttl) => ttl,
+        None => {
+            let config = Config::load(repo)?;
+            parse_duration(&config.tmp_ttl).context("Invalid tmp_ttl in .gwtr.toml")?
+        }
+    };
+    
+    let created_at = now();
+    let temporary = created_at.checked_add(ttl.as_secs())
+        .map(|expires_at| Temporary { created_at, expires_at })
+        .filter(|temporary| temporary.expiry().is_some())
+        .with_context(|| format!("Invalid time to live '{}s': duration too large", ttl.as_secs()))?;
+    
+    let name = next_temporary_name(repo)?;
+    let options = AddOptions { start_point: from.map(str::to_string), ..Default::default() };
+    let path = create_worktree_with(repo, &name, &options)?;
+    
+    Metadata::update(repo, |metadata| {
+        if let Some(entry) = metadata.worktrees.get_mut(&name) {
+            entry.temporary = Some(temporary);
+        }
+    })?;
+    println!("Temporary worktree '{}' expires in {}", name, format_duration(ttl));
+    
+    Ok(path)
+}
+
+/// Delete the branch of a removed temporary worktree, unless it holds commits
+/// that no other branch or tag contains
+///
+/// Returns whether the branch was deleted; the caller reports the outcome.
+pub(crate) fn delete_temporary_branch(workdir: &Path, branch: &str) -> Result<bool> {
+    let own_ref = format!("refs/heads/{}", branch);
+    let contained = refs_containing(workdir, branch)?.iter().any(|reference| *reference != own_ref);
+    if !contained {
+        return Ok(false);
+    }
+    
+    let output = Command::new("git")
+        .args(["branch", "-D", branch])
+        .current_dir(workdir)
+        .output()
+        .context("Failed to execute git branch command")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to delete branch '{}': {}", branch, stderr.trim());
+    }
+    
+    Ok(true)
+}
+
+/// Remove expired temporary worktrees of the repository containing `dir`
+///
+/// Runs on every gwtr invocation, so it stays quiet unless something expired,
+/// reports on stderr to keep the command's own output intact, and never fails
+/// the command. Expired worktrees with uncommitted changes, that
+/// are locked or that contain `dir` are only warned about.
+pub fn expire_temporaries(dir: &Path) {
+    let Ok(repo) = discover_repository(dir) else {
+        return;
+    };
+    let Ok(metadata) = Metadata::load(&repo) else {
+        return;
+    };
+    
+    let expired: Vec<_> = metadata.worktrees.iter()
+        .filter_map(|(name, entry)| Some((name, entry, entry.temporary?.expired_for()?)))
+        .collect();
+    if expired.is_empty() {
+        return;
+    }
+    
+    let Ok(worktrees) = get_worktrees(&repo) else {
+        return;
+    };
+    let current = canonical(dir);
+    
+    for (name, entry, age) in expired {
+        let path = canonical(&entry.path);
+        let Some(worktree) = worktrees.iter().find(|worktree| canonical(&worktree.path) == path) else {
+            continue;
+        };
+        let ago = format_duration(age);
+        
+        let reason = if worktree.locked.is_some() {
+            Some("it is locked".to_string())
+        } else if current.starts_with(&path) {
+            Some("you are inside it".to_string())
+        } else {
+            match dirty_report(&worktree.path) {
+                Ok(report) if report.is_dirty() => Some(format!("it has {} uncommitted changes",
+                                                                report.changed.len() + report.untracked.len())),
+                Ok(_) => None,
+                Err(e) => Some(e.to_string()),
+            }
+        };
+        if let Some(reason) = reason {
+            eprintln!("{}: temporary worktree '{}' expired {} ago but was kept because {} (remove it with `gwtr remove {}`)",
+                      "Warning".yellow(), name, ago, reason, name);
+            continue;
+        }
+        
+        if let Err(e) = remove_expired(&repo, name, &worktree.path, worktree.branch.as_deref()) {
+            eprintln!("{}: failed to remove expired temporary worktree '{}': {:#}", "Warning".yellow(), name, e);
+            continue;
+        }
+        eprintln!("Removed temporary worktree '{}' (expired {} ago)", name, ago);
+    }
+}
+
+/// Remove a clean expired worktree, recording it in the journal like a prune
+fn remove_expired(repo: &Repository, name: &str, path: &Path, branch: Option<&str>) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    
+    let mut journal_entry = JournalEntry::new(Operation::Prune, name);
+    journal_entry.path = Some(path.to_path_buf());
+    journal_entry.branch = branch.map(str::to_string);
+    journal_entry.before = head_commit(path);
+    
+    remove_worktree_directory(workdir, path, false)?;
+    journal_entry.metadata = forget_worktree(repo, name);
+    if let Some(session) = kill_tmux_session_quietly(repo, name) {
+        eprintln!("Killed tmux session '{}'", session);
+    }
+    
+    if let Some(branch) = branch {
+        match delete_temporary_branch(workdir, branch) {
+            Ok(true) => {
+                eprintln!("Deleted branch '{}'", branch.cyan());
+                journal_entry.branch_deleted = true;
+            }
+            Ok(false) => eprintln!("Kept branch '{}': it has commits that are on no other branch", branch.cyan()),
+            Err(e) => eprintln!("{}: {}", "Warning".yellow(), e),
+        }
+    }
+    record_operation(repo, journal_entry);
+    
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+
+use crate::ports::{quote, worktree_env_at};
+use crate::{Config, TmuxConfig, get_repository_name, resolve_worktree_path};
+
+fn tmux(config: &TmuxConfig, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("tmux");
+    if let Some(socket) = &config.socket {
+        command.args(["-L", socket]);
+    }
+    
+    let output = command.args(args)
+        .output()
+        .context("Failed to execute tmux; is it installed?")?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("tmux {} failed: {}", args.first().unwrap_or(&""), stderr.trim());
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Name of the tmux session of a worktree, `{repo}/{name}`
+///
+/// tmux does not allow `.` or `:` in session names, so they become `_`.
+pub fn tmux_session_name(repo: &Repository, worktree_name: &str) -> Result<String> {
+    let name = format!("{}/{}", get_repository_name(repo)?, worktree_name);
+    Ok(name.replace(['.', ':'], "_"))
+}
+
+fn session_exists(config: &TmuxConfig, session: &str) -> bool {
+    tmux(config, &["has-session", "-t", &format!("={}", session)]).is_ok()
+}
+
+/// Create the configured windows and panes of a new session, with `env` set in each pane
+fn create_session(config: &TmuxConfig, session: &str, path: &Path, env: &[(String, String)]) -> Result<()> {
+    let path = path.to_string_lossy();
+    let env_args: Vec<String> = env.iter()
+        .flat_map(|(key, value)| ["-e".to_string(), format!("{}={}", key, value)])
+        .collect();
+    let env_args: Vec<&str> = env_args.iter().map(String::as_str).collect();
+    let default_window = [Default::default()];
+    let windows = if config.windows.is_empty() { &default_window[..] } else { &config.windows[..] };
+    
+    let session_target = format!("={}:", session);
+    for (index, window) in windows.iter().enumerate() {
+        let mut args = if index == 0 {
+            vec!["new-session", "-d", "-s", session, "-c", &path]
+        } else {
+            vec!["new-window", "-t", &session_target, "-c", &path]
+        };
+        if let Some(name) = &window.name {
+            args.extend(["-n", name]);
+        }
+        args.extend(&env_args);
+        args.extend(["-P", "-F", "#{pane_id}"]);
+        let pane = tmux(config, &args)?;
+        
+        // Commands are typed into a shell, so the pane survives when they exit
+        if let Some(command) = &window.command {
+            tmux(config, &["send-keys", "-t", &pane, command, "Enter"])?;
+        }
+        for command in &window.panes {
+            let mut args = vec!["split-window", "-t", &pane, "-c", &path];
+            args.extend(&env_args);
+            args.extend(["-P", "-F", "#{pane_id}"]);
+            let split = tmux(config, &args)?;
+            tmux(config, &["send-keys", "-t", &split, command, "Enter"])?;
+        }
+        if let Some(layout) = &window.layout {
+            tmux(config, &["select-layout", "-t", &pane, layout])?;
+        }
+    }
+    
+    tmux(config, &["select-window", "-t", &format!("{}^", session_target)])?;
+    Ok(())
+}
+
+/// Create a tmux session for a worktree, or reuse the existing one, and attach to it
+///
+/// Outside a terminal the session is only created, and the command to attach printed.
+pub fn tmux_session(repo: &Repository, worktree_name: &str) -> Result<()> {
+    let path = resolve_worktree_path(repo, worktree_name)?;
+    if !path.exists() {
+        bail!("Worktree '{}' not found at {:?}", worktree_name, path);
+    }
+    
+    let config = Config::load(repo)?.tmux;
+    let session = tmux_session_name(repo, worktree_name)?;
+    
+    if session_exists(&config, &session) {
+        println!("Using tmux session '{}'", session);
+    } else {
+        create_session(&config, &session, &path, &worktree_env_at(repo, worktree_name, &path)?)?;
+        println!("Created tmux session '{}'", session);
+    }
+    
+    let target = format!("={}", session);
+    // The hint has to name the private server the session was created on
+    let server = config.socket.as_deref()
+        .map(|socket| format!(" -L {}", quote(socket)))
+        .unwrap_or_default();
+    let attach_hint = || println!("Attach with: tmux{} attach -t '{}'", server, session);
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        attach_hint();
+    } else if std::env::var_os("TMUX").is_some() {
+        // Switching fails when the session lives on another server, e.g. a private socket
+        if tmux(&config, &["switch-client", "-t", &target]).is_err() {
+            attach_hint();
+        }
+    } else {
+        let mut command = Command::new("tmux");
+        if let Some(socket) = &config.socket {
+            command.args(["-L", socket]);
+        }
+        command.args(["attach-session", "-t", &target])
+            .status()
+            .context("Failed to execute tmux attach-session")?;
+    }
+    
+    Ok(())
+}
+
+/// Kill the tmux session of a removed worktree, if there is one
+///
+/// Does nothing when tmux is not installed or no server is running.
+pub fn kill_tmux_session(repo: &Repository, worktree_name: &str) {
+    if let Some(session) = kill_tmux_session_quietly(repo, worktree_name) {
+        println!("Killed tmux session '{}'", session);
+    }
+}
+
+/// Like [`kill_tmux_session`], but leaves reporting the killed session to the caller
+pub(crate) fn kill_tmux_session_quietly(repo: &Repository, worktree_name: &str) -> Option<String> {
+    let (Ok(config), Ok(session)) = (Config::load(repo), tmux_session_name(repo, worktree_name)) else {
+        return None;
+    };
+    
+    if !session_exists(&config.tmux, &session) {
+        return None;
+    }
+    match tmux(&config.tmux, &["kill-session", "-t", &format!("={}", session)]) {
+        Ok(_) => Some(session),
+        Err(e) => {
+            eprintln!("{}: {}", "Warning".yellow(), e);
+            None
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+use crate::{Trim, branch_exists, format_duration, get_command_dir, git, register_worktree, run_git, submodule_report};
+
+/// Namespace of the refs holding uncommitted work saved from deleted worktrees
+pub const TRASH_REF_PREFIX: &str = "refs/gwtr/trash/";
+
+/// Uncommitted work saved from a deleted worktree
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    /// Entry name, `<worktree>/<timestamp>`, with a `.<n>` suffix for further
+    /// entries saved within the same second
+    pub name: String,
+    /// Name of the deleted worktree
+    pub worktree: String,
+    /// Branch that was checked out, if any
+    pub branch: Option<String>,
+    /// Where the worktree was located
+    pub path: PathBuf,
+    /// Unix timestamp of when the work was saved
+    pub timestamp: u64,
+    /// Stash-like commit holding the changes
+    pub commit: String,
+}
+
+impl TrashEntry {
+    fn ref_name(&self) -> String {
+        format!("{}{}", TRASH_REF_PREFIX, self.name)
+    }
+}
+
+/// Environment for the commits of a trash entry: saving work must not fail
+/// just because no identity is configured
+fn commit_identity(dir: &Path) -> Vec<(&'static str, &'static OsStr)> {
+    let has_identity = Command::new("git")
+        .args(["var", "GIT_COMMITTER_IDENT"])
+        .current_dir(dir)
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if has_identity {
+        return Vec::new();
+    }
+    
+    [
+        ("GIT_AUTHOR_NAME", "gwtr"),
+        ("GIT_AUTHOR_EMAIL", "gwtr@localhost"),
+        ("GIT_COMMITTER_NAME", "gwtr"),
+        ("GIT_COMMITTER_EMAIL", "gwtr@localhost"),
+    ].map(|(key, value)| (key, OsStr::new(value))).to_vec()
+}
+
+/// Save the staged, unstaged and untracked changes of a worktree under
+/// `refs/gwtr/trash/<name>/<timestamp>` before it is deleted
+///
+/// The commit has the same shape as one made by `git stash --include-untracked`:
+/// the worktree state with HEAD, the index and the untracked files as parents.
+/// Changes inside submodules live in their own repositories, which are deleted
+/// with the worktree, so a worktree whose submodules have any is refused.
+pub fn save_to_trash(worktree_path: &Path, worktree_name: &str, branch: Option<&str>) -> Result<TrashEntry> {
+    let dirty_submodules = submodule_report(worktree_path)?.dirty;
+    if !dirty_submodules.is_empty() {
+        bail!("Uncommitted changes in submodule(s) {} cannot be saved; commit or discard them first",
+              dirty_submodules.join(", "));
+    }
+    
+    let private_dir = PathBuf::from(git(worktree_path, &["rev-parse", "--absolute-git-dir"])?);
+    let temp_index = private_dir.join("gwtr-trash-index");
+    
+    let head = git(worktree_path, &["rev-parse", "HEAD"])?;
+    let on = branch.unwrap_or("(detached)");
+    let identity = commit_identity(worktree_path);
+    
+    let index_tree = git(worktree_path, &["write-tree"])
+        .context("Failed to save the index; resolve any conflicts first")?;
+    let index_commit = run_git(worktree_path, &[
+        "commit-tree", &index_tree, "-p", &head, "-m", &format!("index on {}", on),
+    ], &identity, Trim::Both)?;
+    
+    let untracked = git(worktree_path, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+    let untracked_commit = if untracked.is_empty() {
+        None
+    } else {
+        // Add only the untracked files to an empty index
+        let pathspec_file = private_dir.join("gwtr-trash-untracked");
+        std::fs::write(&pathspec_file, &untracked)
+            .with_context(|| format!("Failed to write {:?}", pathspec_file))?;
+        let _ = std::fs::remove_file(&temp_index);
+        
+        let pathspec_arg = format!("--pathspec-from-file={}", pathspec_file.display());
+        let index_env = [("GIT_INDEX_FILE", temp_index.as_os_str())];
+        let result = run_git(worktree_path, &["add", &pathspec_arg, "--pathspec-file-nul"], &index_env, Trim::Both)
+            .and_then(|_| run_git(worktree_path, &["write-tree"], &index_env, Trim::Both))
+            .and_then(|tree| run_git(worktree_path, &[
+                "commit-tree", &tree, "-m", &format!("untracked files on {}", on),
+            ], &identity, Trim::Both));
+        let _ = std::fs::remove_file(&temp_index);
+        let _ = std::fs::remove_file(&pathspec_file);
+        Some(result?)
+    };
+    
+    let index_file = private_dir.join("index");
+    std::fs::copy(&index_file, &temp_index)
+        .with_context(|| format!("Failed to copy {:?}", index_file))?;
+    let index_env = [("GIT_INDEX_FILE", temp_index.as_os_str())];
+    let result = run_git(worktree_path, &["add", "--update", "--", "."], &index_env, Trim::Both)
+        .and_then(|_| run_git(worktree_path, &["write-tree"], &index_env, Trim::Both));
+    let _ = std::fs::remove_file(&temp_index);
+    let worktree_tree = result?;
+    
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let message = format!(
+        "gwtr trash: {}\n\ngwtr-worktree: {}\ngwtr-branch: {}\ngwtr-path: {}\n",
+        worktree_name, worktree_name, branch.unwrap_or(""), worktree_path.display(),
+    );
+    
+    let mut args = vec!["commit-tree", &worktree_tree, "-p", &head, "-p", &index_commit];
+    if let Some(untracked_commit) = &untracked_commit {
+        args.extend(["-p", untracked_commit]);
+    }
+    args.extend(["-m", &message]);
+    let commit = run_git(worktree_path, &args, &identity, Trim::Both)?;
+    
+    let mut entry = TrashEntry {
+        name: format!("{}/{}", worktree_name, timestamp),
+        worktree: worktree_name.to_string(),
+        branch: branch.map(str::to_string),
+        path: worktree_path.to_path_buf(),
+        timestamp,
+        commit,
+    };
+    
+    // Only create a new ref, so an entry saved within the same second gets a
+    // `.<n>` suffix instead of replacing the earlier one
+    for n in 2.. {
+        match git(worktree_path, &["update-ref", &entry.ref_name(), &entry.commit, ""]) {
+            Ok(_) => break,
+            Err(_) if git(worktree_path, &["rev-parse", "--verify", "--quiet", &entry.ref_name()]).is_ok() => {
+                entry.name = format!("{}/{}.{}", worktree_name, timestamp, n);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    
+    Ok(entry)
+}
+
+/// List saved trash entries, newest first
+pub fn get_trash_entries(repo_dir: &Path) -> Result<Vec<TrashEntry>> {
+    let output = git(repo_dir, &[
+        "for-each-ref", "--format=%(refname)%00%(objectname)%00%(contents:body)%00%00", TRASH_REF_PREFIX,
+    ])?;
+    
+    let mut entries: Vec<TrashEntry> = output
+        .split("\0\0")
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\0');
+            let ref_name = fields.next()?;
+            let commit = fields.next()?;
+            let body = fields.next().unwrap_or("");
+            
+            let name = ref_name.strip_prefix(TRASH_REF_PREFIX)?;
+            let (worktree, suffix) = name.rsplit_once('/')?;
+            let timestamp = suffix.split_once('.').map_or(suffix, |(timestamp, _)| timestamp);
+            let field = |key: &str| {
+                body.lines()
+                    .find_map(|line| line.strip_prefix(key))
+                    .map(|value| value.trim().to_string())
+            };
+            
+            Some(TrashEntry {
+                name: name.to_string(),
+                worktree: worktree.to_string(),
+                branch: field("gwtr-branch:").filter(|branch| !branch.is_empty()),
+                path: PathBuf::from(field("gwtr-path:")?),
+                timestamp: timestamp.parse().ok()?,
+                commit: commit.to_string(),
+            })
+        })
+        .collect();
+    
+    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// Find a trash entry by its full name, or the newest entry for a worktree name
+fn find_trash_entry(repo_dir: &Path, name: &str) -> Result<TrashEntry> {
+    let entries = get_trash_entries(repo_dir)?;
+    
+    entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .or_else(|| entries.iter().find(|entry| entry.worktree == name))
+        .cloned()
+        .with_context(|| format!("Trash entry '{}' not found (see `gwtr trash list`)", name))
+}
+
+fn saved_ago(timestamp: u64) -> String {
+    let saved_at = SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp);
+    let age = SystemTime::now().duration_since(saved_at).unwrap_or_default();
+    format!("{} ago", format_duration(age))
+}
+
+/// Show the saved trash entries
+pub fn list_trash(repo: &Repository) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    
+    let entries = get_trash_entries(workdir)?;
+    if entries.is_empty() {
+        println!("Trash is empty");
+        return Ok(());
+    }
+    
+    println!("{}", "Trash:".bold());
+    for entry in entries {
+        println!("  {} [{}] at {} (saved {})",
+                 entry.name.yellow(),
+                 entry.branch.as_deref().unwrap_or("detached").cyan(),
+                 entry.path.display(),
+                 saved_ago(entry.timestamp));
+    }
+    
+    Ok(())
+}
+
+/// Permanently delete a trash entry
+pub fn drop_trash(repo: &Repository, name: &str) -> Result<()> {
+    let workdir = get_command_dir(repo);
+    
+    let entry = find_trash_entry(workdir, name)?;
+    git(workdir, &["update-ref", "-d", &entry.ref_name()])?;
+    println!("Dropped trash entry '{}'", entry.name);
+    
+    Ok(())
+}
+
+/// Recreate a deleted worktree and re-apply the changes saved in the trash
+pub fn restore_from_trash(repo: &Repository, name: &str) -> Result<PathBuf> {
+    let workdir = get_command_dir(repo);
+    
+    let entry = find_trash_entry(workdir, name)?;
+    if entry.path.exists() {
+        bail!("Cannot restore '{}': {:?} already exists", entry.name, entry.path);
+    }
+    
+    let path = entry.path.to_string_lossy().to_string();
+    let base = format!("{}^1", entry.commit);
+    match entry.branch.as_deref() {
+        Some(branch) => {
+            if branch_exists(workdir, branch) {
+                git(workdir, &["worktree", "add", &path, branch])?;
+            } else {
+                git(workdir, &["worktree", "add", "-b", branch, &path, &base])?;
+            }
+        }
+        None => {
+            git(workdir, &["worktree", "add", "--detach", &path, &base])?;
+        }
+    }
+    
+    git(&entry.path, &["stash", "apply", "--index", &entry.commit])
+        .with_context(|| format!("Recreated worktree at {:?} but failed to re-apply the saved changes", entry.path))?;
+    git(workdir, &["update-ref", "-d", &entry.ref_name()])?;
+    register_worktree(repo, &entry.worktree, &entry.path, entry.branch.as_deref());
+    
+    println!("Restored worktree '{}' at {:?}", entry.worktree, entry.path);
+    println!("cd '{}'", entry.path.display());
+    
+    Ok(entry.path)
+}
@@ -0,0 +1,246 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use git2::Repository;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::journal::undo_operation;
+use crate::{
+    AddOptions, Config, JournalEntry, MergeRule, RemoveOptions, UntrackedFiles, canonical, confirm,
+    create_worktree_journaled, detect_merge, dirty_report, get_command_dir, get_worktree_path,
+    get_worktrees, is_main_worktree, remove_worktree_journaled, resolve_worktree_path,
+    show_worktrees_status, worktree_name,
+};
+
+/// Name of the workspace manifest, looked up in the current directory and its parents
+pub const WORKSPACE_FILE_NAME: &str = "gwtr-workspace.toml";
+
+/// A set of repositories whose worktrees are created and removed together
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workspace {
+    /// Directory containing the manifest; repository paths are relative to it
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// Repositories in the workspace
+    pub repos: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Find the manifest in `dir` or the nearest parent directory that has one
+    pub fn discover(dir: &Path) -> Result<Workspace> {
+        let Some(root) = dir.ancestors().find(|dir| dir.join(WORKSPACE_FILE_NAME).is_file()) else {
+            bail!("No {} found in {:?} or its parents", WORKSPACE_FILE_NAME, dir);
+        };
+        
+        let path = root.join(WORKSPACE_FILE_NAME);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        let mut workspace: Workspace = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?}", path))?;
+        workspace.root = root.to_path_buf();
+        
+        Ok(workspace)
+    }
+    
+    /// Open every repository, failing if any of them is missing
+    fn repositories(&self) -> Result<Vec<(String, Repository)>> {
+        self.repos
+            .iter()
+            .map(|path| {
+                let label = path.display().to_string();
+                let repo = Repository::open(self.root.join(path))
+                    .with_context(|| format!("{}: not a git repository", label))?;
+                Ok((label, repo))
+            })
+            .collect()
+    }
+}
+
+/// Reverse the operations this command performed, newest first, after a partial failure
+///
+/// Each operation is reversed from its own journal entry rather than from the end of
+/// the journal, so an entry that failed to be recorded cannot make the rollback
+/// reverse an unrelated operation.
+fn roll_back(done: &[(&str, &Repository, JournalEntry)]) {
+    for (label, repo, entry) in done.iter().rev() {
+        println!("{}:", label.bold());
+        if let Err(e) = undo_operation(repo, entry) {
+            eprintln!("{}: rollback failed in {}, fix manually: {}", "Warning".yellow(), label, e);
+        }
+    }
+}
+
+/// Create a worktree and branch named `name` in every repository of the workspace
+pub fn workspace_add(workspace: &Workspace, name: &str) -> Result<()> {
+    let repos = workspace.repositories()?;
+    
+    for (label, repo) in &repos {
+        let path = get_worktree_path(repo, name)?;
+        if path.exists() {
+            bail!("{}: worktree '{}' already exists at {:?}", label, name, path);
+        }
+    }
+    
+    let mut created = Vec::new();
+    for (label, repo) in &repos {
+        println!("{}:", label.bold());
+        match create_worktree_journaled(repo, name, &AddOptions::default()) {
+            Ok((_, entry)) => created.push((label.as_str(), repo, entry)),
+            Err(e) => {
+                println!("Rolling back...");
+                roll_back(&created);
+                bail!("{}: {:#}; no worktrees were created", label, e);
+            }
+        }
+    }
+    
+    println!("\nCreated '{}' in {} repositories", name, created.len());
+    
+    Ok(())
+}
+
+/// Show the status of the worktrees of every repository in the workspace
+pub fn workspace_status(workspace: &Workspace, untracked: UntrackedFiles) -> Result<()> {
+    for (label, repo) in workspace.repositories()? {
+        println!("{}:", label.bold());
+        show_worktrees_status(&repo, untracked)?;
+    }
+    
+    Ok(())
+}
+
+/// Remove the worktrees named `name` from every repository that has one, or none of them
+pub fn workspace_remove(workspace: &Workspace, name: &str, force: bool) -> Result<()> {
+    let repos = workspace.repositories()?;
+    let mut targets = Vec::new();
+    
+    // Check every worktree before removing any of them
+    for entry in &repos {
+        let (label, repo) = entry;
+        let path = resolve_worktree_path(repo, name)?;
+        if !path.exists() {
+            continue;
+        }
+        
+        let locked = get_worktrees(repo)?
+            .iter()
+            .any(|worktree| worktree.locked.is_some() && canonical(&worktree.path) == canonical(&path));
+        if locked {
+            bail!("{}: worktree '{}' is locked; unlock it first", label, name);
+        }
+        if !force && dirty_report(&path)?.is_dirty() {
+            bail!("{}: worktree '{}' has uncommitted changes (use --force to remove it anyway)", label, name);
+        }
+        
+        targets.push(entry);
+    }
+    
+    if targets.is_empty() {
+        bail!("No repository in the workspace has a worktree named '{}'", name);
+    }
+    
+    let options = RemoveOptions { force, ..RemoveOptions::default() };
+    let mut removed = Vec::new();
+    for (label, repo) in targets {
+        println!("{}:", label.bold());
+        let mut entry = None;
+        let result = remove_worktree_journaled(repo, name, &options, &mut entry);
+        // A removal that failed after the worktree was gone is rolled back as well
+        if let Some(entry) = entry {
+            removed.push((label.as_str(), repo, entry));
+        }
+        if let Err(e) = result {
+            println!("Rolling back...");
+            roll_back(&removed);
+            bail!("{}: {:#}; no worktrees were removed", label, e);
+        }
+    }
+    
+    println!("\nRemoved '{}' from {} repositories", name, removed.len());
+    
+    Ok(())
+}
+
+/// Remove every feature whose worktrees are merged, clean and unlocked in all
+/// repositories that have one
+pub fn workspace_prune(workspace: &Workspace, dry_run: bool, force: bool) -> Result<()> {
+    let repos = workspace.repositories()?;
+    
+    // Feature name -> (repository, merge rule) for each worktree, or a reason to keep it
+    let mut features: BTreeMap<String, Result<Vec<(String, MergeRule)>, String>> = BTreeMap::new();
+    
+    for (label, repo) in &repos {
+        let config = Config::load(repo)?;
+        let command_dir = get_command_dir(repo);
+        
+        for worktree in get_worktrees(repo)? {
+            if worktree.is_bare || is_main_worktree(repo, &worktree.path) {
+                continue;
+            }
+            let Some(branch) = worktree.branch.as_deref().filter(|branch| *branch != config.base_branch) else {
+                continue;
+            };
+            
+            let name = worktree_name(repo, &worktree.path)?;
+            let verdict = if worktree.locked.is_some() {
+                Err(format!("locked in {}", label))
+            } else if dirty_report(&worktree.path)?.is_dirty() {
+                Err(format!("uncommitted changes in {}", label))
+            } else {
+                match detect_merge(command_dir, branch, &config.base_branch)? {
+                    Some(rule) => Ok(rule),
+                    None => Err(format!("not merged in {}", label)),
+                }
+            };
+            
+            let feature = features.entry(name).or_insert_with(|| Ok(Vec::new()));
+            match (feature, verdict) {
+                (Ok(merged), Ok(rule)) => merged.push((label.clone(), rule)),
+                (feature @ Ok(_), Err(reason)) => *feature = Err(reason),
+                (Err(_), _) => {}
+            }
+        }
+    }
+    
+    let selected: Vec<(String, Vec<(String, MergeRule)>)> = features
+        .into_iter()
+        .filter_map(|(name, verdict)| verdict.ok().map(|merged| (name, merged)))
+        .collect();
+    
+    if selected.is_empty() {
+        println!("No features to prune");
+        return Ok(());
+    }
+    
+    println!("{}", if dry_run {
+        format!("Would prune {} feature(s):", selected.len())
+    } else {
+        format!("Found {} feature(s) to prune:", selected.len())
+    }.bold());
+    for (name, merged) in &selected {
+        let repos: Vec<String> = merged.iter().map(|(label, rule)| format!("{} ({})", label, rule)).collect();
+        println!("  {}: {}", name.yellow(), repos.join(", "));
+    }
+    
+    if dry_run {
+        return Ok(());
+    }
+    if !force && !confirm("\nPrune these features?")? {
+        println!("Aborted");
+        return Ok(());
+    }
+    
+    let mut pruned = 0;
+    for (name, _) in &selected {
+        println!();
+        match workspace_remove(workspace, name, false) {
+            Ok(()) => pruned += 1,
+            Err(e) => eprintln!("{}: failed to prune '{}': {:#}", "Error".red(), name, e),
+        }
+    }
+    
+    println!("\nPruned {} feature(s)", pruned);
+    
+    Ok(())
+}
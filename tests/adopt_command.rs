@@ -0,0 +1,108 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_adopted_worktree_is_addressed_by_name() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("somewhere");
+    helper.git(&["worktree", "add", "-b", "experiment", custom.to_str().unwrap()]);
+    
+    let output = helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "exp"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("as 'exp'"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["lock", "exp"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    helper.run_gwtr(&["unlock", "exp"]);
+    
+    let output = helper.run_gwtr(&["remove", "exp"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!custom.exists());
+}
+
+#[test]
+fn test_adopt_relocates_to_layout_path() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("somewhere");
+    helper.git(&["worktree", "add", "-b", "experiment", custom.to_str().unwrap()]);
+    
+    let output = helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "exp", "--relocate"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    assert!(!custom.exists());
+    let branch = helper.git_in(&helper.worktree_path("exp"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "experiment");
+}
+
+#[test]
+fn test_readopting_keeps_ports() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    let port = |name: &str| {
+        let output = helper.run_gwtr(&["env", name]);
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).lines()
+            .find_map(|line| line.strip_prefix("PORT=").map(str::to_string))
+            .unwrap()
+    };
+    let original = port("feature-x");
+    
+    let path = helper.worktree_path("feature-x");
+    let output = helper.run_gwtr(&["adopt", path.to_str().unwrap(), "--name", "renamed"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    // The port block stays with the worktree instead of going to the next one
+    helper.run_gwtr(&["add", "feature-y"]);
+    assert_ne!(port("feature-y"), original);
+    assert_eq!(port("renamed"), original);
+}
+
+#[test]
+fn test_prune_uses_adopted_name() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("somewhere");
+    helper.git(&["worktree", "add", "-b", "experiment", custom.to_str().unwrap()]);
+    helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "exp"]);
+    
+    let output = helper.run_gwtr(&["prune", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("exp [experiment]"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_adopt_refuses_name_in_use() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    
+    let custom = helper.repo_path.with_extension("somewhere");
+    helper.git(&["worktree", "add", "-b", "experiment", custom.to_str().unwrap()]);
+    
+    let output = helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "feature-x"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already used"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_adopt_refuses_unknown_directory() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("plain");
+    std::fs::create_dir(&custom).unwrap();
+    
+    let output = helper.run_gwtr(&["adopt", custom.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is not a worktree"), "stderr: {}", stderr);
+}
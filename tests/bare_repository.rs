@@ -0,0 +1,75 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_add_places_worktrees_in_project_directory() {
+    let helper = TestHelper::new().unwrap();
+    let project = helper.init_bare_layout();
+    
+    let output = helper.run_gwtr_in(&project, &["add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(project.join("feature-x").exists());
+    
+    let output = helper.run_gwtr_in(&project, &["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[bare]"), "Actual output: {}", stdout);
+    assert!(stdout.contains("feature-x [feature-x]"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("(main)"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_commands_work_from_linked_worktree() {
+    let helper = TestHelper::new().unwrap();
+    let project = helper.init_bare_layout();
+    
+    let output = helper.run_gwtr_in(&project.join("main"), &["add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(project.join("feature-x").exists());
+    
+    let output = helper.run_gwtr_in(&project.join("main"), &["remove", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!project.join("feature-x").exists());
+}
+
+#[test]
+fn test_status_and_doctor_in_bare_layout() {
+    let helper = TestHelper::new().unwrap();
+    let project = helper.init_bare_layout();
+    helper.run_gwtr_in(&project, &["add", "feature-x"]);
+    
+    let output = helper.run_gwtr_in(&project, &["status"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[feature-x] - clean"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr_in(&project, &["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No problems found"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_considers_main_named_worktree() {
+    let helper = TestHelper::new().unwrap();
+    let project = helper.init_bare_layout();
+    helper.run_gwtr_in(&project, &["add", "feature-x"]);
+    
+    // In a bare layout the worktree on the base branch is not special, but its
+    // branch is the base itself and is never pruned
+    let output = helper.run_gwtr_in(&project, &["prune", "--dry-run"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("feature-x [feature-x]"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("main [main]"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_pull_current_requires_worktree() {
+    let helper = TestHelper::new().unwrap();
+    let project = helper.init_bare_layout();
+    
+    let output = helper.run_gwtr_in(&project, &["pull"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Not inside a worktree"), "stderr: {}", stderr);
+}
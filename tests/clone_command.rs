@@ -0,0 +1,100 @@
+mod common;
+
+use common::TestHelper;
+use tempfile::TempDir;
+
+fn url(helper: &TestHelper) -> String {
+    format!("file://{}", helper.repo_path.display())
+}
+
+#[test]
+fn test_clone_main_layout() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let target = TempDir::new().unwrap();
+    
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper), "app"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Layout (main clone)"), "Actual output: {}", stdout);
+    assert!(stdout.contains("app_<name>"), "Actual output: {}", stdout);
+    
+    assert!(target.path().join("app/.git").is_dir());
+    assert!(target.path().join("app/README.md").exists());
+}
+
+#[test]
+fn test_clone_bare_layout() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let target = TempDir::new().unwrap();
+    
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper), "app", "--layout", "bare"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Layout (bare repository)"), "Actual output: {}", stdout);
+    
+    let project = target.path().join("app");
+    assert!(project.join(".bare").is_dir());
+    assert!(project.join("main/README.md").exists());
+    
+    // Fetching updates remote-tracking branches and the base branch tracks origin
+    let refspec = helper.git_in(&project, &["config", "remote.origin.fetch"]);
+    assert_eq!(refspec, "+refs/heads/*:refs/remotes/origin/*");
+    helper.git_in(&project, &["rev-parse", "--verify", "refs/remotes/origin/main"]);
+    let upstream = helper.git_in(&project.join("main"), &["rev-parse", "--abbrev-ref", "@{upstream}"]);
+    assert_eq!(upstream, "origin/main");
+    
+    // The new layout is usable right away
+    let output = helper.run_gwtr_in(&project, &["add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(project.join("feature-x").exists());
+}
+
+#[test]
+fn test_clone_records_default_branch_other_than_main() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.git(&["branch", "-m", "main", "trunk"]);
+    let target = TempDir::new().unwrap();
+    
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper), "app", "--layout", "bare"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let config = std::fs::read_to_string(target.path().join("app/.gwtr.toml")).unwrap();
+    assert!(config.contains("base_branch = \"trunk\""), "Config: {}", config);
+    
+    // A clone of a repository whose default branch is main needs no configuration
+    helper.git(&["branch", "-m", "trunk", "main"]);
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper), "other"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!target.path().join("other/.gwtr.toml").exists());
+}
+
+#[test]
+fn test_clone_uses_user_config_layout_and_derives_directory() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let target = TempDir::new().unwrap();
+    std::fs::create_dir_all(helper.config_home.join("gwtr")).unwrap();
+    std::fs::write(helper.config_home.join("gwtr/config.toml"), "layout = \"bare\"\n").unwrap();
+    
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper)]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let name = helper.repo_path.file_name().unwrap();
+    assert!(target.path().join(name).join(".bare").is_dir());
+}
+
+#[test]
+fn test_clone_refuses_non_empty_directory() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let target = TempDir::new().unwrap();
+    std::fs::create_dir(target.path().join("app")).unwrap();
+    std::fs::write(target.path().join("app/file"), "content").unwrap();
+    
+    let output = helper.run_gwtr_in(target.path(), &["clone", &url(&helper), "app"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not empty"), "stderr: {}", stderr);
+}
@@ -1,11 +1,16 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use std::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 /// Helper struct for running gwtr commands in tests
 pub struct TestHelper {
     _temp_dir: TempDir,  // Keeps the directory alive until dropped
     pub repo_path: PathBuf,
+    _config_dir: TempDir,
+    /// User-level configuration directory, used as `XDG_CONFIG_HOME`
+    pub config_home: PathBuf,
 }
 
 impl TestHelper {
@@ -20,15 +25,100 @@ impl TestHelper {
             .current_dir(&repo_path)
             .output()?;
         
-        Ok(Self { _temp_dir: temp_dir, repo_path })
+        // Keep the user-level configuration of the machine out of the tests
+        let config_dir = TempDir::new()?;
+        let config_home = config_dir.path().to_path_buf();
+        
+        Ok(Self { _temp_dir: temp_dir, repo_path, _config_dir: config_dir, config_home })
     }
     
     /// Run gwtr command with arguments
+    #[allow(dead_code)]
     pub fn run_gwtr(&self, args: &[&str]) -> std::process::Output {
+        self.run_gwtr_in(&self.repo_path, args)
+    }
+    
+    /// Run gwtr command with arguments in `dir`
+    #[allow(dead_code)]
+    pub fn run_gwtr_in(&self, dir: &Path, args: &[&str]) -> std::process::Output {
+        Command::new(env!("CARGO_BIN_EXE_gwtr"))
+            .args(args)
+            .current_dir(dir)
+            .env("XDG_CONFIG_HOME", &self.config_home)
+            .output()
+            .expect("Failed to execute gwtr")
+    }
+    
+    /// Run gwtr command with arguments and extra environment variables
+    #[allow(dead_code)]
+    pub fn run_gwtr_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> std::process::Output {
         Command::new(env!("CARGO_BIN_EXE_gwtr"))
             .args(args)
             .current_dir(&self.repo_path)
+            .env("XDG_CONFIG_HOME", &self.config_home)
+            .env_remove("VISUAL")
+            .env_remove("EDITOR")
+            .envs(env.iter().copied())
             .output()
             .expect("Failed to execute gwtr")
     }
-}
\ No newline at end of file
+    
+    /// Run a git command in `dir`, panicking if it fails, and return its stdout
+    #[allow(dead_code)]
+    pub fn git_in(&self, dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Failed to execute git");
+        assert!(output.status.success(),
+                "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+    
+    /// Run a git command in the test repository
+    #[allow(dead_code)]
+    pub fn git(&self, args: &[&str]) -> String {
+        self.git_in(&self.repo_path, args)
+    }
+    
+    /// Configure a committer and create an initial commit on a `main` branch
+    #[allow(dead_code)]
+    pub fn init_main(&self) {
+        self.git(&["symbolic-ref", "HEAD", "refs/heads/main"]);
+        self.git(&["config", "user.email", "test@example.com"]);
+        self.git(&["config", "user.name", "Test User"]);
+        std::fs::write(self.repo_path.join("README.md"), "Initial").unwrap();
+        self.git(&["add", "."]);
+        self.git(&["commit", "-m", "Initial commit"]);
+    }
+    
+    /// Create a bare layout cloned from the test repository: `project/.bare`, a
+    /// `project/.git` file pointing to it and a `project/main` worktree
+    #[allow(dead_code)]
+    pub fn init_bare_layout(&self) -> PathBuf {
+        self.init_main();
+        let project = self.repo_path.join("project");
+        self.git(&["clone", "--bare", ".", "project/.bare"]);
+        std::fs::write(project.join(".git"), "gitdir: ./.bare\n").unwrap();
+        self.git_in(&project, &["config", "user.email", "test@example.com"]);
+        self.git_in(&project, &["config", "user.name", "Test User"]);
+        self.git_in(&project, &["worktree", "add", "main", "main"]);
+        project
+    }
+    
+    /// Commit a file with the given content in `dir`
+    #[allow(dead_code)]
+    pub fn commit_file(&self, dir: &Path, file: &str, content: &str) {
+        std::fs::write(dir.join(file), content).unwrap();
+        self.git_in(dir, &["add", file]);
+        self.git_in(dir, &["commit", "-m", &format!("Update {}", file)]);
+    }
+    
+    /// Path where gwtr places the worktree with the given name
+    #[allow(dead_code)]
+    pub fn worktree_path(&self, name: &str) -> PathBuf {
+        let repo_name = self.repo_path.file_name().unwrap().to_str().unwrap();
+        self.repo_path.parent().unwrap().join(format!("{}_{}", repo_name, name))
+    }
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::TestHelper;
+
+/// Tag the initial commit as `v1.0` and move `main` past it
+fn init_with_tag(helper: &TestHelper) -> String {
+    helper.init_main();
+    helper.git(&["tag", "v1.0"]);
+    helper.commit_file(&helper.repo_path, "later.txt", "later");
+    helper.git(&["rev-parse", "--short=7", "v1.0"])
+}
+
+#[test]
+fn test_add_detach_checks_out_ref_without_branch() {
+    let helper = TestHelper::new().unwrap();
+    let short = init_with_tag(&helper);
+    
+    let output = helper.run_gwtr(&["add", "release", "--detach", "v1.0"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(detached at v1.0)"), "Actual output: {}", stdout);
+    
+    let worktree = helper.worktree_path("release");
+    assert!(!worktree.join("later.txt").exists());
+    assert!(helper.git(&["branch", "--list", "release"]).is_empty());
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("[detached at v1.0 ({})]", short)), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["status"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("[detached at v1.0 ({})] - clean", short)), "Actual output: {}", stdout);
+    
+    // Once HEAD moves away from the ref, only the commit is shown
+    helper.git_in(&worktree, &["checkout", "--quiet", "--detach", "main"]);
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let head = helper.git(&["rev-parse", "--short=7", "main"]);
+    assert!(stdout.contains(&format!("[detached at {}]", head)), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_add_detach_unknown_ref_fails() {
+    let helper = TestHelper::new().unwrap();
+    init_with_tag(&helper);
+    
+    let output = helper.run_gwtr(&["add", "bisect", "--detach", "v9.9"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown ref 'v9.9'"));
+    assert!(!helper.worktree_path("bisect").exists());
+}
+
+#[test]
+fn test_pull_explains_detached_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    init_with_tag(&helper);
+    helper.run_gwtr(&["add", "release", "--detach", "v1.0"]);
+    
+    let output = helper.run_gwtr(&["pull", "release"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Worktree 'release' is detached at v1.0"), "stderr: {}", stderr);
+    assert!(stderr.contains("no branch to pull into"), "stderr: {}", stderr);
+    
+    let output = helper.run_gwtr(&["pull", "--all"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipped: detached HEAD, no branch to pull into"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_detached_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    init_with_tag(&helper);
+    helper.run_gwtr(&["add", "release", "--detach", "v1.0"]);
+    helper.run_gwtr(&["add", "bisect", "--detach", "v1.0"]);
+    helper.git_in(&helper.worktree_path("bisect"), &["checkout", "--quiet", "--detach", "main"]);
+    helper.run_gwtr(&["add", "experiment", "--detach", "main"]);
+    let experiment = helper.worktree_path("experiment");
+    helper.commit_file(&experiment, "experiment.txt", "only here");
+    
+    // Only the worktree that moved on to a commit of the base branch is selected
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(detached HEAD contained in main)"), "Actual output: {}", stdout);
+    assert!(!helper.worktree_path("bisect").exists());
+    assert!(helper.worktree_path("release").exists());
+    assert!(experiment.exists());
+    
+    // Age rules select the tag's worktree too, but never lose commits
+    let output = helper.run_gwtr(&["prune", "--older-than", "0s", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 1 worktree:"), "Actual output: {}", stdout);
+    assert!(stdout.contains("release [detached at v1.0"), "Actual output: {}", stdout);
+    assert!(stdout.contains("its commits are on no branch or tag and would be lost"), "Actual output: {}", stdout);
+}
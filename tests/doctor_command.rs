@@ -0,0 +1,95 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_doctor_reports_no_problems() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    
+    let output = helper.run_gwtr(&["doctor"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No problems found"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_repair_prunes_deleted_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    std::fs::remove_dir_all(helper.worktree_path("feature-x")).unwrap();
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("prunable"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("is stale"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["repair"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let worktrees = helper.git(&["worktree", "list"]);
+    assert!(!worktrees.contains("feature-x"), "Worktrees: {}", worktrees);
+    let output = helper.run_gwtr(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No problems found"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_repair_relinks_moved_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    std::fs::rename(helper.worktree_path("feature-x"), helper.worktree_path("feature-y")).unwrap();
+    
+    let output = helper.run_gwtr(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("was moved to"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["repair"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    // The worktree is usable from its new location and still on its branch
+    let branch = helper.git_in(&helper.worktree_path("feature-y"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "feature-x");
+    let worktrees = helper.git(&["worktree", "list"]);
+    assert!(worktrees.contains("feature-y"), "Worktrees: {}", worktrees);
+}
+
+#[test]
+fn test_doctor_reports_manual_problems() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    
+    // An orphan directory in the layout and a branch checked out twice
+    std::fs::create_dir(helper.worktree_path("leftover")).unwrap();
+    let second = helper.worktree_path("second");
+    helper.git(&["worktree", "add", "--force", second.to_str().unwrap(), "feature-x"]);
+    
+    let output = helper.run_gwtr(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("is not a registered worktree"), "Actual output: {}", stdout);
+    assert!(stdout.contains("branch 'feature-x' is checked out in"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["repair"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 problem(s) need manual attention"), "Actual output: {}", stdout);
+    assert!(helper.worktree_path("leftover").exists());
+}
+
+#[test]
+fn test_doctor_reports_missing_base_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    std::fs::write(helper.repo_path.join(".gwtr.toml"), "base_branch = \"develop\"\n").unwrap();
+    
+    let output = helper.run_gwtr(&["doctor"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("base branch 'develop' does not exist"), "Actual output: {}", stdout);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::TestHelper;
+
+fn write_artifacts(helper: &TestHelper, dir: &std::path::Path) {
+    helper.commit_file(dir, ".gitignore", "target/\nbuild.log\n");
+    std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+    std::fs::write(dir.join("target/debug/app"), vec![0u8; 4096]).unwrap();
+    std::fs::write(dir.join("build.log"), vec![0u8; 100]).unwrap();
+}
+
+#[test]
+fn test_du_reports_tracked_ignored_and_artifact_sizes() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-du"]);
+    write_artifacts(&helper, &helper.worktree_path("feature-du"));
+    
+    let output = helper.run_gwtr(&["du"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    
+    let row = stdout.lines().find(|line| line.starts_with("feature-du")).unwrap();
+    assert!(row.contains("4.1 KB"), "Actual output: {}", stdout);
+    assert!(row.contains("4.0 KB"), "Actual output: {}", stdout);
+    assert!(stdout.lines().any(|line| line.starts_with("main")), "Actual output: {}", stdout);
+    assert!(stdout.lines().last().unwrap().starts_with("Total"), "Actual output: {}", stdout);
+    
+    // The largest worktree comes first
+    let first = stdout.lines().nth(1).unwrap();
+    assert!(first.starts_with("feature-du"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_du_sort_by_name_and_select_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-a"]);
+    helper.run_gwtr(&["add", "feature-b"]);
+    
+    let output = helper.run_gwtr(&["du", "--sort", "name"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let names: Vec<&str> = stdout.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(names, ["feature-a", "feature-b", "main", "Total"], "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["du", "feature-b"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("feature-b"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("feature-a"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_du_clean_artifacts_deletes_only_matching_ignored_directories() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-clean"]);
+    helper.run_gwtr(&["add", "feature-keep"]);
+    let clean = helper.worktree_path("feature-clean");
+    let keep = helper.worktree_path("feature-keep");
+    write_artifacts(&helper, &clean);
+    write_artifacts(&helper, &keep);
+    
+    let output = helper.run_gwtr(&["du", "feature-clean", "--clean-artifacts", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Freed 4.0 KB"), "Actual output: {}", stdout);
+    
+    assert!(!clean.join("target").exists());
+    assert!(clean.join("build.log").exists());
+    assert!(clean.join(".gitignore").exists());
+    assert!(keep.join("target").exists());
+}
+
+#[test]
+fn test_du_clean_artifacts_uses_configured_patterns() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    std::fs::write(helper.repo_path.join(".gwtr.toml"), "artifact_patterns = [\"dist\"]\n").unwrap();
+    helper.run_gwtr(&["add", "feature-dist"]);
+    let worktree = helper.worktree_path("feature-dist");
+    write_artifacts(&helper, &worktree);
+    std::fs::create_dir_all(worktree.join("dist")).unwrap();
+    std::fs::write(worktree.join("dist/bundle.js"), "x").unwrap();
+    std::fs::write(worktree.join(".gitignore"), "target/\nbuild.log\ndist/\n").unwrap();
+    
+    let output = helper.run_gwtr(&["du", "feature-dist", "--clean-artifacts", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree.join("dist").exists());
+    assert!(worktree.join("target").exists());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::TestHelper;
+
+fn env_value(helper: &TestHelper, name: &str, key: &str) -> String {
+    let output = helper.run_gwtr(&["env", name]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    stdout.lines()
+        .find_map(|line| line.strip_prefix(&format!("{}=", key)))
+        .unwrap_or_else(|| panic!("{} missing from: {}", key, stdout))
+        .to_string()
+}
+
+#[test]
+fn test_add_allocates_distinct_port_blocks() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["add", "feature-a"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Ports 4000-4009"), "Actual output: {}", stdout);
+    helper.run_gwtr(&["add", "feature-b"]);
+    
+    assert_eq!(env_value(&helper, "feature-a", "PORT"), "4000");
+    assert_eq!(env_value(&helper, "feature-b", "GWTR_PORT"), "4010");
+    assert_eq!(env_value(&helper, "feature-b", "GWTR_PORT_9"), "4019");
+    assert_eq!(env_value(&helper, "feature-b", "GWTR_BRANCH"), "feature-b");
+    
+    // The block stays the same across invocations
+    assert_eq!(env_value(&helper, "feature-a", "PORT"), "4000");
+}
+
+#[test]
+fn test_remove_frees_port_block() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-a"]);
+    helper.run_gwtr(&["add", "feature-b"]);
+    
+    helper.run_gwtr(&["remove", "feature-a"]);
+    helper.run_gwtr(&["add", "feature-c"]);
+    
+    assert_eq!(env_value(&helper, "feature-c", "PORT"), "4000");
+    assert_eq!(env_value(&helper, "feature-b", "PORT"), "4010");
+}
+
+#[test]
+fn test_port_blocks_do_not_collide_across_repositories() {
+    let first = TestHelper::new().unwrap();
+    first.init_main();
+    let second = TestHelper::new().unwrap();
+    second.init_main();
+    
+    // Both repositories share the first helper's user-level configuration
+    first.run_gwtr(&["add", "feature-a"]);
+    let output = first.run_gwtr_in(&second.repo_path, &["add", "feature-b"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Ports 4010-4019"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_env_file_is_written_and_ignored() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    std::fs::write(
+        helper.repo_path.join(".gwtr.toml"),
+        "env_file = \".env\"\nport_base = 5000\nport_block_size = 3\n",
+    ).unwrap();
+    
+    helper.run_gwtr(&["add", "feature-env"]);
+    let worktree = helper.worktree_path("feature-env");
+    let content = std::fs::read_to_string(worktree.join(".env")).unwrap();
+    assert!(content.contains("PORT=5000\n"), "Content: {}", content);
+    assert!(content.contains("GWTR_PORT_2=5002\n"), "Content: {}", content);
+    assert!(!content.contains("GWTR_PORT_3"), "Content: {}", content);
+    
+    // Settings outside gwtr's block survive a rewrite
+    std::fs::write(worktree.join(".env"), format!("DEBUG=1\n{}", content)).unwrap();
+    let output = helper.run_gwtr(&["env", "feature-env", "--write"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let rewritten = std::fs::read_to_string(worktree.join(".env")).unwrap();
+    assert_eq!(rewritten, format!("DEBUG=1\n{}", content));
+    
+    // The env file does not count as uncommitted work
+    let output = helper.run_gwtr(&["remove", "feature-env"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree.exists());
+}
+
+#[test]
+fn test_env_unknown_worktree_fails() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["env", "missing"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_list_global_shows_every_used_repository() {
+    let first = TestHelper::new().unwrap();
+    first.init_main();
+    let second = TestHelper::new().unwrap();
+    second.init_main();
+    
+    // Both repositories share the first helper's user-level configuration
+    first.run_gwtr(&["add", "feature-a"]);
+    first.run_gwtr_in(&second.repo_path, &["add", "feature-b"]);
+    
+    let outside = tempfile::TempDir::new().unwrap();
+    let output = first.run_gwtr_in(outside.path(), &["list", "--global"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[feature-a]"), "Actual output: {}", stdout);
+    assert!(stdout.contains("[feature-b]"), "Actual output: {}", stdout);
+    
+    let output = first.run_gwtr_in(outside.path(), &["status", "--global"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[feature-a] - clean"), "Actual output: {}", stdout);
+    assert!(stdout.contains("[feature-b] - clean"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_global_dry_run() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-merged"]);
+    
+    let outside = tempfile::TempDir::new().unwrap();
+    let output = helper.run_gwtr_in(outside.path(), &["prune", "--global", "--dry-run"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 1 worktree"), "Actual output: {}", stdout);
+    assert!(helper.worktree_path("feature-merged").exists());
+}
+
+#[test]
+fn test_global_forgets_vanished_repositories() {
+    let helper = TestHelper::new().unwrap();
+    let gone = tempfile::TempDir::new().unwrap();
+    helper.git_in(gone.path(), &["init"]);
+    helper.run_gwtr_in(gone.path(), &["list"]);
+    let gone_path = gone.path().canonicalize().unwrap();
+    drop(gone);
+    
+    let output = helper.run_gwtr_in(&helper.config_home, &["list", "--global"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Forgetting"), "Actual output: {}", stdout);
+    
+    let registry = std::fs::read_to_string(helper.config_home.join("gwtr/repositories.json")).unwrap();
+    assert!(!registry.contains(gone_path.to_str().unwrap()), "Registry: {}", registry);
+}
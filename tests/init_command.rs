@@ -0,0 +1,90 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_init_writes_detected_defaults() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.git(&["branch", "-m", "main", "trunk"]);
+    
+    let output = helper.run_gwtr(&["init"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let config = std::fs::read_to_string(helper.repo_path.join(".gwtr.toml")).unwrap();
+    assert!(config.contains("base_branch = \"trunk\""), "Config: {}", config);
+    assert!(config.contains("remote = \"origin\""), "Config: {}", config);
+    assert!(config.contains("layout = \"main\""), "Config: {}", config);
+    
+    // Running it again does not overwrite the file
+    let output = helper.run_gwtr(&["init"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_init_escapes_detected_names() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.git(&["branch", "-m", "main", "release\"2"]);
+    
+    let output = helper.run_gwtr(&["init"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    // The written file is valid TOML that gwtr reads back
+    let output = helper.run_gwtr(&["add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let config = std::fs::read_to_string(helper.repo_path.join(".gwtr.toml")).unwrap();
+    assert!(config.contains(r#"base_branch = 'release"2'"#) || config.contains(r#"base_branch = "release\"2""#),
+            "Config: {}", config);
+}
+
+#[test]
+fn test_init_migrates_worktrees_to_template_paths() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("elsewhere");
+    helper.git(&["worktree", "add", "-b", "feature-x", custom.to_str().unwrap()]);
+    
+    let output = helper.run_gwtr(&["init", "--migrate"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Moved 1 worktree(s)"), "Actual output: {}", stdout);
+    
+    assert!(!custom.exists());
+    let moved = helper.repo_path.parent().unwrap().join(format!(
+        "{}_{}",
+        helper.repo_path.file_name().unwrap().to_str().unwrap(),
+        custom.file_name().unwrap().to_str().unwrap(),
+    ));
+    let branch = helper.git_in(&moved, &["branch", "--show-current"]);
+    assert_eq!(branch, "feature-x");
+}
+
+#[test]
+fn test_init_refuses_to_migrate_dirty_or_locked_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let dirty = helper.repo_path.with_extension("dirty");
+    let locked = helper.repo_path.with_extension("locked");
+    helper.git(&["worktree", "add", "-b", "dirty", dirty.to_str().unwrap()]);
+    helper.git(&["worktree", "add", "-b", "locked", locked.to_str().unwrap()]);
+    helper.git(&["worktree", "lock", locked.to_str().unwrap()]);
+    std::fs::write(dirty.join("scratch.txt"), "work in progress").unwrap();
+    
+    let output = helper.run_gwtr(&["init", "--migrate"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("has uncommitted changes"), "stderr: {}", stderr);
+    assert!(stderr.contains("is locked"), "stderr: {}", stderr);
+    
+    // Nothing was moved or written
+    assert!(dirty.exists());
+    assert!(locked.exists());
+    assert!(!helper.repo_path.join(".gwtr.toml").exists());
+    
+    helper.git(&["worktree", "unlock", locked.to_str().unwrap()]);
+    helper.git(&["worktree", "remove", "--force", dirty.to_str().unwrap()]);
+    helper.git(&["worktree", "remove", locked.to_str().unwrap()]);
+}
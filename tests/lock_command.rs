@@ -0,0 +1,80 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_lock_shows_reason_in_list_and_status() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "release-1"]);
+    let output = helper.run_gwtr(&["lock", "release-1", "--reason", "release branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("locked: release branch"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["status"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("locked: release branch"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["unlock", "release-1"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("locked"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_skips_locked_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "benchmark"]);
+    helper.run_gwtr(&["lock", "benchmark"]);
+    
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--include-locked"), "Actual output: {}", stdout);
+    assert!(stdout.contains("No worktrees to prune"), "Actual output: {}", stdout);
+    assert!(helper.worktree_path("benchmark").exists());
+    
+    let output = helper.run_gwtr(&["prune", "--force", "--include-locked"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Pruned 1 worktree"), "Actual output: {}", stdout);
+    assert!(!helper.worktree_path("benchmark").exists());
+}
+
+#[test]
+fn test_pull_all_skips_locked_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "benchmark"]);
+    helper.run_gwtr(&["lock", "benchmark", "--reason", "pinned"]);
+    
+    let output = helper.run_gwtr(&["pull", "--all"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let locked_line = stdout.lines().find(|line| line.contains("benchmark")).unwrap();
+    assert!(locked_line.contains("Skipped") && locked_line.contains("locked: pinned"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["pull", "--all", "--include-locked"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let locked_line = stdout.lines().find(|line| line.contains("benchmark")).unwrap();
+    assert!(!locked_line.contains("locked"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_lock_unknown_worktree_fails() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["lock", "missing"]);
+    assert!(!output.status.success());
+}
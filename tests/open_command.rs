@@ -0,0 +1,120 @@
+mod common;
+
+use common::TestHelper;
+
+fn set_editor(helper: &TestHelper, editor: &str) {
+    std::fs::write(helper.repo_path.join(".gwtr.toml"), format!("editor = {:?}\n", editor)).unwrap();
+}
+
+#[test]
+fn test_open_runs_configured_template() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "echo {name} > {path}/opened");
+    helper.run_gwtr(&["add", "feature-open"]);
+    
+    let output = helper.run_gwtr_with_env(&["open", "feature-open"], &[]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let opened = std::fs::read_to_string(helper.worktree_path("feature-open").join("opened")).unwrap();
+    assert_eq!(opened, "feature-open\n");
+}
+
+#[test]
+fn test_open_passes_worktree_environment() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "echo $GWTR_WORKTREE $GWTR_PORT > {path}/opened");
+    helper.run_gwtr(&["add", "feature-env"]);
+    
+    let output = helper.run_gwtr_with_env(&["open", "feature-env"], &[]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let opened = std::fs::read_to_string(helper.worktree_path("feature-env").join("opened")).unwrap();
+    assert_eq!(opened, "feature-env 4000\n");
+}
+
+#[test]
+fn test_open_without_name_outside_named_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "echo $GWTR_WORKTREE_PATH > {path}/opened");
+    
+    // The main worktree
+    let output = helper.run_gwtr(&["open"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.repo_path.join("opened").exists());
+    
+    // A worktree created by `git worktree add` outside the layout
+    let elsewhere = helper.repo_path.with_extension("elsewhere");
+    helper.git(&["worktree", "add", "-b", "elsewhere", elsewhere.to_str().unwrap()]);
+    let output = helper.run_gwtr_in(&elsewhere, &["open"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let opened = std::fs::read_to_string(elsewhere.join("opened")).unwrap();
+    assert_eq!(opened.trim(), elsewhere.display().to_string());
+}
+
+#[test]
+fn test_open_resolves_adopted_names() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "touch {path}/opened");
+    let custom = helper.repo_path.with_extension("open-custom");
+    helper.git(&["worktree", "add", "-b", "custom", custom.to_str().unwrap()]);
+    helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "experiment"]);
+    
+    let output = helper.run_gwtr_with_env(&["open", "experiment"], &[]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(custom.join("opened").exists());
+}
+
+#[test]
+fn test_open_falls_back_to_editor_variable() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-editor"]);
+    let worktree = helper.worktree_path("feature-editor");
+    
+    // The path is appended to $EDITOR
+    let editor = "sh -c 'touch \"$0/opened\"'";
+    let output = helper.run_gwtr_with_env(&["open", "feature-editor"], &[("EDITOR", editor)]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree.join("opened").exists());
+    
+    let output = helper.run_gwtr_with_env(&["open", "feature-editor"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No editor configured"));
+}
+
+#[test]
+fn test_open_current_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "touch {path}/opened");
+    helper.run_gwtr(&["add", "feature-current"]);
+    let worktree = helper.worktree_path("feature-current");
+    
+    let output = helper.run_gwtr_in(&worktree, &["open"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree.join("opened").exists());
+}
+
+#[test]
+fn test_add_open() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "touch {path}/opened");
+    
+    let output = helper.run_gwtr(&["add", "feature-new", "--open"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.worktree_path("feature-new").join("opened").exists());
+}
+
+#[test]
+fn test_open_missing_worktree_fails() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    set_editor(&helper, "true");
+    
+    let output = helper.run_gwtr(&["open", "missing"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 mod common;
 
 use common::TestHelper;
@@ -171,4 +173,337 @@ fn test_prune_shows_what_would_be_removed() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     // Should list worktrees or say nothing to prune
     assert!(stdout.contains("worktree") || stdout.contains("No worktrees to prune"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_prune_detects_squash_and_rebase_merges() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    // A branch merged with a squash merge
+    helper.run_gwtr(&["add", "squashed"]);
+    let squashed_path = helper.worktree_path("squashed");
+    helper.commit_file(&squashed_path, "a.txt", "a");
+    helper.commit_file(&squashed_path, "b.txt", "b");
+    
+    // A branch merged by rebasing its commit onto main
+    helper.run_gwtr(&["add", "rebased"]);
+    let rebased_path = helper.worktree_path("rebased");
+    helper.commit_file(&rebased_path, "c.txt", "c");
+    
+    // A branch that was never merged
+    helper.run_gwtr(&["add", "unmerged"]);
+    helper.commit_file(&helper.worktree_path("unmerged"), "d.txt", "d");
+    
+    helper.commit_file(&helper.repo_path, "main.txt", "main moved on");
+    helper.git(&["merge", "--squash", "squashed"]);
+    helper.git(&["commit", "-m", "Squash merge"]);
+    helper.git(&["cherry-pick", "rebased"]);
+    
+    let output = helper.run_gwtr(&["prune", "--dry-run"]);
+    assert!(output.status.success());
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 2 worktrees"), "Actual output: {}", stdout);
+    assert!(stdout.contains("squashed") && stdout.contains("(squash-merged)"), "Actual output: {}", stdout);
+    assert!(stdout.contains("rebased") && stdout.contains("(rebase-merged)"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("unmerged"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_detects_identical_tree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "same-tree"]);
+    let worktree_path = helper.worktree_path("same-tree");
+    helper.commit_file(&worktree_path, "a.txt", "first");
+    helper.commit_file(&worktree_path, "a.txt", "second");
+    
+    // Main ends up with exactly the branch's tree through different commits
+    helper.commit_file(&helper.repo_path, "a.txt", "second");
+    
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(output.status.success());
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(identical tree)"), "Actual output: {}", stdout);
+    assert!(stdout.contains("Pruned 1 worktree"), "Actual output: {}", stdout);
+    assert!(!worktree_path.exists());
+}
+
+#[test]
+fn test_prune_gone_selects_deleted_upstream_branches() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    // Set up a remote and push two branches with upstream tracking
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    helper.git_in(remote_dir.path(), &["init", "--bare"]);
+    helper.git(&["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+    helper.git(&["push", "-u", "origin", "main"]);
+    
+    helper.run_gwtr(&["add", "feature-done"]);
+    helper.run_gwtr(&["add", "feature-open"]);
+    for name in ["feature-done", "feature-open"] {
+        let worktree_path = helper.worktree_path(name);
+        helper.commit_file(&worktree_path, &format!("{}.txt", name), name);
+        helper.git_in(&worktree_path, &["push", "-u", "origin", name]);
+    }
+    
+    // The remote deletes the branch after its pull request is merged
+    helper.git_in(remote_dir.path(), &["branch", "-D", "feature-done"]);
+    
+    let output = helper.run_gwtr(&["prune", "--gone", "--dry-run"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 1 worktree:"), "Actual output: {}", stdout);
+    assert!(stdout.contains("(upstream origin/feature-done gone)"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("feature-open"), "Actual output: {}", stdout);
+    
+    // Without --gone the unmerged branch is not selected
+    let output = helper.run_gwtr(&["prune", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No worktrees to prune"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["prune", "--gone", "--force"]);
+    assert!(output.status.success());
+    assert!(!helper.worktree_path("feature-done").exists());
+    assert!(helper.worktree_path("feature-open").exists());
+}
+
+/// Commit a file in `dir` with author and committer dates `days_ago` days in the past
+fn commit_file_days_ago(dir: &std::path::Path, file: &str, days_ago: u64) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() - days_ago * 24 * 60 * 60;
+    let date = format!("@{} +0000", timestamp);
+    
+    std::fs::write(dir.join(file), file).unwrap();
+    Command::new("git")
+        .args(["add", file])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let output = Command::new("git")
+        .args(["commit", "-m", &format!("Add {}", file)])
+        .env("GIT_AUTHOR_DATE", &date)
+        .env("GIT_COMMITTER_DATE", &date)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+/// Set the modification time of every checked out file in `dir` to `days_ago` days in the past
+fn touch_files_days_ago(dir: &std::path::Path, days_ago: u64) {
+    let mtime = std::time::SystemTime::now() - std::time::Duration::from_secs(days_ago * 24 * 60 * 60);
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_file() {
+            std::fs::File::options().write(true).open(entry.path()).unwrap()
+                .set_modified(mtime).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_prune_older_than_and_inactive_for() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "abandoned"]);
+    let abandoned_path = helper.worktree_path("abandoned");
+    commit_file_days_ago(&abandoned_path, "abandoned.txt", 60);
+    touch_files_days_ago(&abandoned_path, 60);
+    
+    // Old commits, but the files were touched recently
+    helper.run_gwtr(&["add", "in-progress"]);
+    commit_file_days_ago(&helper.worktree_path("in-progress"), "in-progress.txt", 60);
+    
+    helper.run_gwtr(&["add", "recent"]);
+    commit_file_days_ago(&helper.worktree_path("recent"), "recent.txt", 1);
+    
+    let output = helper.run_gwtr(&["prune", "--older-than", "30d", "--dry-run"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 2 worktrees:"), "Actual output: {}", stdout);
+    assert!(stdout.contains("last commit 60d ago"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("recent"), "Actual output: {}", stdout);
+    
+    // Both criteria must match
+    let output = helper.run_gwtr(&["prune", "--older-than", "30d", "--inactive-for", "4w", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 1 worktree:"), "Actual output: {}", stdout);
+    assert!(stdout.contains("abandoned"), "Actual output: {}", stdout);
+    assert!(stdout.contains("inactive for 60d"), "Actual output: {}", stdout);
+    assert!(!stdout.contains("in-progress"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_skips_dirty_worktrees_unless_included() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "dirty-experiment"]);
+    let worktree_path = helper.worktree_path("dirty-experiment");
+    commit_file_days_ago(&worktree_path, "experiment.txt", 90);
+    std::fs::write(worktree_path.join("notes.txt"), "unsaved work").unwrap();
+    
+    let output = helper.run_gwtr(&["prune", "--older-than", "30d", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipping"), "Actual output: {}", stdout);
+    assert!(stdout.contains("--include-dirty"), "Actual output: {}", stdout);
+    assert!(stdout.contains("No worktrees to prune"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["prune", "--older-than", "30d", "--include-dirty", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would prune 1 worktree:"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_rejects_invalid_duration() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["prune", "--inactive-for", "soon"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(gwtr::parse_duration("90s").unwrap().as_secs(), 90);
+    assert_eq!(gwtr::parse_duration("2w").unwrap().as_secs(), 2 * 7 * 24 * 60 * 60);
+    assert!(gwtr::parse_duration("3y").is_err());
+    
+    let error = gwtr::parse_duration("99999999999999999w").unwrap_err();
+    assert!(error.to_string().contains("duration too large"), "Error: {}", error);
+}
+
+#[test]
+fn test_prune_rejects_too_large_duration() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["prune", "--older-than", "99999999999999999w", "--dry-run"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("duration too large"), "stderr: {}", stderr);
+    assert!(!stderr.contains("panicked"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_prune_delete_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-merged"]);
+    
+    let output = helper.run_gwtr(&["prune", "--force", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Deleted branch"), "Actual output: {}", stdout);
+    assert!(helper.git(&["branch", "--list", "feature-merged"]).is_empty());
+}
+
+#[test]
+fn test_prune_delete_branch_from_another_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-other"]);
+    helper.commit_file(&helper.worktree_path("feature-other"), "other.txt", "other");
+    helper.run_gwtr(&["add", "feature-merged"]);
+    helper.commit_file(&helper.worktree_path("feature-merged"), "merged.txt", "merged");
+    helper.git(&["merge", "--no-ff", "-m", "Merge feature-merged", "feature-merged"]);
+    
+    let output = helper.run_gwtr_in(&helper.worktree_path("feature-other"), &["prune", "--force", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!helper.worktree_path("feature-merged").exists());
+    assert!(helper.git(&["branch", "--list", "feature-merged"]).is_empty());
+}
+
+#[test]
+fn test_prune_force_does_not_delete_unmerged_branches() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    helper.git_in(remote_dir.path(), &["init", "--bare"]);
+    helper.git(&["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+    helper.git(&["push", "-u", "origin", "main"]);
+    
+    helper.run_gwtr(&["add", "feature-gone"]);
+    let worktree_path = helper.worktree_path("feature-gone");
+    helper.commit_file(&worktree_path, "pushed.txt", "pushed");
+    helper.git_in(&worktree_path, &["push", "-u", "origin", "feature-gone"]);
+    helper.commit_file(&worktree_path, "unpushed.txt", "unpushed");
+    helper.git_in(remote_dir.path(), &["branch", "-D", "feature-gone"]);
+    
+    // --force only skips the confirmation; the worktree is kept along with its branch
+    let output = helper.run_gwtr(&["prune", "--gone", "--force", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("branch is not merged into 'main' (use --delete-unmerged"), "Actual output: {}", stdout);
+    assert!(worktree_path.exists());
+    assert!(!helper.git(&["branch", "--list", "feature-gone"]).is_empty());
+    
+    let output = helper.run_gwtr(&["prune", "--gone", "--force", "--delete-branch", "--delete-unmerged"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree_path.exists());
+    assert!(helper.git(&["branch", "--list", "feature-gone"]).is_empty());
+}
+
+#[test]
+fn test_prune_include_dirty_shows_what_would_be_lost() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-dirty"]);
+    let worktree_path = helper.worktree_path("feature-dirty");
+    std::fs::write(worktree_path.join("scratch.txt"), "unsaved").unwrap();
+    
+    // Without --force the confirmation prompt cannot be answered, so nothing is removed
+    let output = helper.run_gwtr(&["prune", "--include-dirty"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("uncommitted changes that would be lost"), "Actual output: {}", stdout);
+    assert!(stdout.contains("scratch.txt"), "Actual output: {}", stdout);
+    assert!(stdout.contains("Cancelled"), "Actual output: {}", stdout);
+    assert!(worktree_path.exists());
+    
+    let output = helper.run_gwtr(&["prune", "--include-dirty", "--force"]);
+    assert!(output.status.success());
+    assert!(!worktree_path.exists());
+}
+
+#[test]
+fn test_prune_counts_only_removed_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-nested"]);
+    helper.run_gwtr(&["add", "feature-plain"]);
+    
+    // git refuses to remove a worktree containing a nested repository committed as a gitlink
+    let worktree = helper.worktree_path("feature-nested");
+    helper.git_in(&worktree, &["init", "nested"]);
+    helper.git_in(&worktree.join("nested"), &["config", "user.email", "test@example.com"]);
+    helper.git_in(&worktree.join("nested"), &["config", "user.name", "Test User"]);
+    helper.git_in(&worktree.join("nested"), &["commit", "--allow-empty", "-m", "Nested"]);
+    helper.git_in(&worktree, &["add", "nested"]);
+    helper.git_in(&worktree, &["commit", "-m", "Add nested repository"]);
+    helper.git(&["merge", "feature-nested"]);
+    
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("Pruned 1 worktree\n"), "Actual output: {}", stdout);
+    assert!(stderr.contains("Failed to prune 1 worktree"), "stderr: {}", stderr);
+    assert!(worktree.exists());
+    assert!(!helper.worktree_path("feature-plain").exists());
+}
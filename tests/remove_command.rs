@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 mod common;
 
 use common::TestHelper;
@@ -101,4 +103,167 @@ fn test_remove_shows_confirmation_message() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("Removed worktree") || stdout.contains("feature-confirm"),
             "Should show confirmation message");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_remove_delete_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    // A branch merged through a squash merge is deleted
+    helper.run_gwtr(&["add", "feature-merged"]);
+    helper.commit_file(&helper.worktree_path("feature-merged"), "merged.txt", "merged");
+    helper.git(&["merge", "--squash", "feature-merged"]);
+    helper.git(&["commit", "-m", "Squash merge"]);
+    
+    let output = helper.run_gwtr(&["remove", "feature-merged", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Deleted branch"), "Actual output: {}", stdout);
+    assert!(helper.git(&["branch", "--list", "feature-merged"]).is_empty());
+    
+    // So is a branch merged with a regular merge commit
+    helper.run_gwtr(&["add", "feature-regular"]);
+    helper.commit_file(&helper.worktree_path("feature-regular"), "regular.txt", "regular");
+    helper.git(&["merge", "--no-ff", "-m", "Merge feature-regular", "feature-regular"]);
+    
+    let output = helper.run_gwtr(&["remove", "feature-regular", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git(&["branch", "--list", "feature-regular"]).is_empty());
+}
+
+#[test]
+fn test_remove_delete_branch_from_another_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-other"]);
+    helper.run_gwtr(&["add", "feature-merged"]);
+    helper.commit_file(&helper.worktree_path("feature-merged"), "merged.txt", "merged");
+    helper.git(&["merge", "--no-ff", "-m", "Merge feature-merged", "feature-merged"]);
+    
+    // feature-other does not contain the merge, but the branch is merged into main
+    let output = helper.run_gwtr_in(&helper.worktree_path("feature-other"), &["remove", "feature-merged", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git(&["branch", "--list", "feature-merged"]).is_empty());
+}
+
+#[test]
+fn test_remove_delete_branch_refuses_unmerged() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-wip"]);
+    helper.commit_file(&helper.worktree_path("feature-wip"), "wip.txt", "wip");
+    
+    // Unmerged branches are refused and the worktree is left alone
+    let output = helper.run_gwtr(&["remove", "feature-wip", "--delete-branch"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not merged"), "Actual error: {}", stderr);
+    assert!(helper.worktree_path("feature-wip").exists());
+    
+    // --force deletes it anyway
+    let output = helper.run_gwtr(&["remove", "feature-wip", "--delete-branch", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!helper.worktree_path("feature-wip").exists());
+    assert!(helper.git(&["branch", "--list", "feature-wip"]).is_empty());
+}
+
+#[test]
+fn test_remove_delete_branch_names_configured_base_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.git(&["branch", "trunk"]);
+    fs::write(helper.repo_path.join(".gwtr.toml"), "base_branch = \"trunk\"\n").unwrap();
+    
+    helper.run_gwtr(&["add", "feature-wip"]);
+    helper.commit_file(&helper.worktree_path("feature-wip"), "wip.txt", "wip");
+    
+    let output = helper.run_gwtr(&["remove", "feature-wip", "--delete-branch"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not merged into 'trunk'"), "Actual error: {}", stderr);
+}
+
+#[test]
+fn test_remove_delete_branch_from_config() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    fs::write(helper.repo_path.join(".gwtr.toml"), "delete_branch = true\n").unwrap();
+    
+    helper.run_gwtr(&["add", "feature-a"]);
+    helper.run_gwtr(&["add", "feature-b"]);
+    
+    let output = helper.run_gwtr(&["remove", "feature-a"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git(&["branch", "--list", "feature-a"]).is_empty());
+    
+    // --keep-branch overrides the configured default
+    let output = helper.run_gwtr(&["remove", "feature-b", "--keep-branch"]);
+    assert!(output.status.success());
+    assert!(!helper.git(&["branch", "--list", "feature-b"]).is_empty());
+}
+
+#[test]
+fn test_remove_delete_remote_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    helper.git_in(remote_dir.path(), &["init", "--bare"]);
+    helper.git(&["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+    helper.git(&["push", "-u", "origin", "main"]);
+    
+    helper.run_gwtr(&["add", "feature-pushed"]);
+    helper.git_in(&helper.worktree_path("feature-pushed"), &["push", "-u", "origin", "feature-pushed"]);
+    
+    let output = helper.run_gwtr(&["remove", "feature-pushed", "--delete-branch", "--delete-remote"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git_in(remote_dir.path(), &["branch", "--list", "feature-pushed"]).is_empty());
+}
+
+#[test]
+fn test_remove_refuses_worktree_with_uncommitted_changes() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-dirty"]);
+    let worktree_path = helper.worktree_path("feature-dirty");
+    fs::write(worktree_path.join("README.md"), "Changed").unwrap();
+    fs::write(worktree_path.join("notes.txt"), "Unsaved notes").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-dirty"]);
+    assert!(!output.status.success());
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("README.md") && stdout.contains("notes.txt"), "Actual output: {}", stdout);
+    assert!(stdout.contains("1 file changed"), "Should show a diffstat. Actual output: {}", stdout);
+    assert!(stderr.contains("--force"), "Actual error: {}", stderr);
+    assert!(worktree_path.join("notes.txt").exists(), "Uncommitted work must be kept");
+    
+    let output = helper.run_gwtr(&["remove", "feature-dirty", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree_path.exists());
+}
+
+#[test]
+fn test_remove_does_not_treat_ignored_files_as_changes() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    fs::write(helper.repo_path.join(".gitignore"), "target/\n").unwrap();
+    helper.git(&["add", ".gitignore"]);
+    helper.git(&["commit", "-m", "Ignore build output"]);
+    
+    helper.run_gwtr(&["add", "feature-built"]);
+    let worktree_path = helper.worktree_path("feature-built");
+    fs::create_dir(worktree_path.join("target")).unwrap();
+    fs::write(worktree_path.join("target/app"), "binary").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-built"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 ignored file"), "Actual output: {}", stdout);
+    assert!(!worktree_path.exists());
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_rename_moves_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    let output = helper.run_gwtr(&["rename", "feature-x", "feature-y"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    assert!(!helper.worktree_path("feature-x").exists());
+    assert!(helper.worktree_path("feature-y").exists());
+    
+    // Without --branch the branch keeps its name
+    let branch = helper.git_in(&helper.worktree_path("feature-y"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "feature-x");
+    
+    let metadata = std::fs::read_to_string(helper.repo_path.join(".git/gwtr/worktrees.json")).unwrap();
+    assert!(metadata.contains("feature-y"), "Metadata: {}", metadata);
+    assert!(!metadata.contains("\"feature-x\":"), "Metadata: {}", metadata);
+}
+
+#[test]
+fn test_move_is_an_alias_for_rename() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    let output = helper.run_gwtr(&["move", "feature-x", "feature-y"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    assert!(!helper.worktree_path("feature-x").exists());
+    assert!(helper.worktree_path("feature-y").exists());
+}
+
+#[test]
+fn test_rename_with_branch_keeps_upstream() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    helper.git(&["config", "branch.feature-x.remote", "origin"]);
+    helper.git(&["config", "branch.feature-x.merge", "refs/heads/feature-x"]);
+    
+    let output = helper.run_gwtr(&["rename", "feature-x", "feature-y", "--branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let branch = helper.git_in(&helper.worktree_path("feature-y"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "feature-y");
+    let remote = helper.git(&["config", "branch.feature-y.remote"]);
+    assert_eq!(remote.trim(), "origin");
+}
+
+#[test]
+fn test_rename_rolls_back_when_branch_exists() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    helper.git(&["branch", "feature-y"]);
+    
+    let output = helper.run_gwtr(&["rename", "feature-x", "feature-y", "--branch"]);
+    assert!(!output.status.success());
+    
+    assert!(helper.worktree_path("feature-x").exists());
+    assert!(!helper.worktree_path("feature-y").exists());
+    let branch = helper.git_in(&helper.worktree_path("feature-x"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "feature-x");
+}
+
+#[test]
+fn test_rename_refuses_existing_target() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    helper.run_gwtr(&["add", "feature-y"]);
+    
+    let output = helper.run_gwtr(&["rename", "feature-x", "feature-y"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already exists"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_rename_refuses_registered_name() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let custom = helper.repo_path.with_extension("custom");
+    helper.git(&["worktree", "add", "-b", "custom", custom.to_str().unwrap()]);
+    helper.run_gwtr(&["adopt", custom.to_str().unwrap(), "--name", "feature-x"]);
+    helper.run_gwtr(&["add", "feature-y"]);
+    
+    let output = helper.run_gwtr(&["rename", "feature-y", "feature-x"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already used"), "stderr: {}", stderr);
+    assert!(helper.worktree_path("feature-y").exists());
+    
+    // feature-x still addresses the adopted worktree
+    let output = helper.run_gwtr(&["env", "feature-x"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("GWTR_WORKTREE_PATH={}", custom.display())), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_undo_rename() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    helper.run_gwtr(&["rename", "feature-x", "feature-y", "--branch"]);
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    assert!(helper.worktree_path("feature-x").exists());
+    assert!(!helper.worktree_path("feature-y").exists());
+    let branch = helper.git_in(&helper.worktree_path("feature-x"), &["branch", "--show-current"]);
+    assert_eq!(branch.trim(), "feature-x");
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::TestHelper;
+
+/// Commit a small monorepo with two services and configure profiles for them
+fn init_monorepo(helper: &TestHelper) {
+    helper.init_main();
+    for dir in ["services/api", "services/web", "libs/common"] {
+        std::fs::create_dir_all(helper.repo_path.join(dir)).unwrap();
+        std::fs::write(helper.repo_path.join(dir).join("file.txt"), dir).unwrap();
+    }
+    helper.git(&["add", "."]);
+    helper.git(&["commit", "-m", "Add services"]);
+    std::fs::write(
+        helper.repo_path.join(".gwtr.toml"),
+        "[sparse_profiles]\napi = [\"services/api\", \"libs/common\"]\n",
+    ).unwrap();
+}
+
+#[test]
+fn test_add_sparse_checks_out_profile_directories() {
+    let helper = TestHelper::new().unwrap();
+    init_monorepo(&helper);
+    
+    let output = helper.run_gwtr(&["add", "feature-api", "--sparse", "api"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Sparse checkout: api (services/api, libs/common)"), "Actual output: {}", stdout);
+    
+    let worktree = helper.worktree_path("feature-api");
+    assert!(worktree.join("services/api/file.txt").exists());
+    assert!(worktree.join("libs/common/file.txt").exists());
+    assert!(worktree.join("README.md").exists());
+    assert!(!worktree.join("services/web").exists());
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|line| line.contains("[feature-api]")).unwrap();
+    assert!(line.contains("sparse: api (services/api, libs/common)"), "Actual output: {}", stdout);
+    
+    // A sparse worktree is clean, so it can be removed without --force
+    let output = helper.run_gwtr(&["remove", "feature-api"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_add_unknown_sparse_profile_fails_before_creating() {
+    let helper = TestHelper::new().unwrap();
+    init_monorepo(&helper);
+    
+    let output = helper.run_gwtr(&["add", "feature-x", "--sparse", "mobile"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown sparse profile 'mobile'; configured profiles: api"), "stderr: {}", stderr);
+    assert!(!helper.worktree_path("feature-x").exists());
+}
+
+#[test]
+fn test_sparse_add_and_set_adjust_worktree() {
+    let helper = TestHelper::new().unwrap();
+    init_monorepo(&helper);
+    helper.run_gwtr(&["add", "feature-adjust", "--sparse", "api"]);
+    let worktree = helper.worktree_path("feature-adjust");
+    
+    let output = helper.run_gwtr(&["sparse", "feature-adjust", "add", "services/web"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree.join("services/web/file.txt").exists());
+    let stdout = String::from_utf8_lossy(&helper.run_gwtr(&["list"]).stdout).to_string();
+    assert!(stdout.contains("sparse: api (libs/common, services/api, services/web)"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["sparse", "feature-adjust", "set", "services/web"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree.join("services/web/file.txt").exists());
+    assert!(!worktree.join("services/api").exists());
+    let stdout = String::from_utf8_lossy(&helper.run_gwtr(&["list"]).stdout).to_string();
+    assert!(stdout.contains("sparse: services/web"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_sparse_set_on_full_worktree() {
+    let helper = TestHelper::new().unwrap();
+    init_monorepo(&helper);
+    helper.run_gwtr(&["add", "feature-full"]);
+    let worktree = helper.worktree_path("feature-full");
+    
+    let output = helper.run_gwtr(&["sparse", "feature-full", "add", "libs/common"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree.join("libs/common/file.txt").exists());
+    assert!(!worktree.join("services").exists());
+    
+    // The main worktree is not affected
+    assert!(helper.repo_path.join("services/web/file.txt").exists());
+}
+
+#[test]
+fn test_status_counts_sparse_worktree_as_clean() {
+    let helper = TestHelper::new().unwrap();
+    init_monorepo(&helper);
+    helper.run_gwtr(&["add", "feature-status", "--sparse", "api"]);
+    
+    let output = helper.run_gwtr(&["status"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[feature-status] - clean"), "Actual output: {}", stdout);
+}
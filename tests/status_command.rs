@@ -93,4 +93,45 @@ fn test_status_with_no_worktrees() {
     assert!(stdout.contains("Worktrees:"));
     // Should still show main worktree
     assert!(stdout.contains("(main)"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_status_untracked_files_modes() {
+    let helper = TestHelper::new().unwrap();
+    
+    // Create initial commit
+    std::fs::write(helper.repo_path.join("README.md"), "Initial").unwrap();
+    std::process::Command::new("git")
+        .args(["add", "."])
+        .current_dir(&helper.repo_path)
+        .output()
+        .unwrap();
+    std::process::Command::new("git")
+        .args(["-c", "user.name=Test User", "-c", "user.email=test@example.com", "commit", "-m", "Initial commit"])
+        .current_dir(&helper.repo_path)
+        .output()
+        .unwrap();
+    
+    // Create an untracked directory with two files
+    std::fs::create_dir(helper.repo_path.join("scratch")).unwrap();
+    std::fs::write(helper.repo_path.join("scratch/a.txt"), "a").unwrap();
+    std::fs::write(helper.repo_path.join("scratch/b.txt"), "b").unwrap();
+    
+    // Normal mode collapses the untracked directory into one entry
+    let output = helper.run_gwtr(&["status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 uncommitted changes"), "Actual output: {}", stdout);
+    
+    // All mode reports every untracked file
+    let output = helper.run_gwtr(&["status", "--untracked-files", "all"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 uncommitted changes"), "Actual output: {}", stdout);
+    
+    // No mode ignores untracked files
+    let output = helper.run_gwtr(&["status", "-u", "no"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("clean"), "Actual output: {}", stdout);
+}
@@ -0,0 +1,246 @@
+mod common;
+
+use common::TestHelper;
+
+/// Local submodule URLs are refused unless the file protocol is allowed
+const ALLOW_FILE_PROTOCOL: [(&str, &str); 3] = [
+    ("GIT_CONFIG_COUNT", "1"),
+    ("GIT_CONFIG_KEY_0", "protocol.file.allow"),
+    ("GIT_CONFIG_VALUE_0", "always"),
+];
+
+/// Add a submodule at `lib` whose repository lives in `library`
+fn add_submodule(helper: &TestHelper, library: &std::path::Path) {
+    helper.git_in(library, &["init", "-b", "main"]);
+    helper.git_in(library, &["config", "user.email", "test@example.com"]);
+    helper.git_in(library, &["config", "user.name", "Test User"]);
+    helper.commit_file(library, "lib.txt", "library");
+    
+    helper.git(&["-c", "protocol.file.allow=always", "submodule", "add", library.to_str().unwrap(), "lib"]);
+    helper.git(&["commit", "-m", "Add submodule"]);
+}
+
+#[test]
+fn test_add_submodules_uses_main_worktree_modules_as_reference() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    
+    let output = helper.run_gwtr_with_env(&["add", "feature-sub", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Initialised 1 submodule(s), 1 using objects from existing clones"), "Actual output: {}", stdout);
+    
+    let worktree = helper.worktree_path("feature-sub");
+    assert_eq!(std::fs::read_to_string(worktree.join("lib/lib.txt")).unwrap(), "library");
+    
+    let module_dir = helper.git_in(&worktree.join("lib"), &["rev-parse", "--absolute-git-dir"]);
+    let alternates = std::fs::read_to_string(std::path::Path::new(&module_dir).join("objects/info/alternates")).unwrap();
+    assert!(alternates.contains(".git/modules/lib"), "Alternates: {}", alternates);
+}
+
+#[test]
+fn test_submodules_config_initialises_on_every_add() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    std::fs::write(helper.repo_path.join(".gwtr.toml"), "submodules = true\n").unwrap();
+    
+    let output = helper.run_gwtr_with_env(&["add", "feature-config"], &ALLOW_FILE_PROTOCOL);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.worktree_path("feature-config").join("lib/lib.txt").exists());
+}
+
+#[test]
+fn test_status_reports_submodule_drift_and_dirtiness() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    
+    helper.run_gwtr(&["add", "feature-uninit"]);
+    helper.run_gwtr_with_env(&["add", "feature-drift", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    helper.run_gwtr_with_env(&["add", "feature-dirty", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    
+    let drift = helper.worktree_path("feature-drift").join("lib");
+    helper.git_in(&drift, &["config", "user.email", "test@example.com"]);
+    helper.git_in(&drift, &["config", "user.name", "Test User"]);
+    helper.commit_file(&drift, "new.txt", "new");
+    std::fs::write(helper.worktree_path("feature-dirty").join("lib/lib.txt"), "changed").unwrap();
+    
+    let output = helper.run_gwtr(&["status"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let submodules_of = |name: &str| {
+        let mut lines = stdout.lines().skip_while(|line| !line.contains(&format!("[{}]", name)));
+        lines.next();
+        lines.next().unwrap_or_default().to_string()
+    };
+    
+    assert_eq!(submodules_of("feature-uninit").trim(), "submodules: 1 uninitialized (lib)", "Actual output: {}", stdout);
+    assert_eq!(submodules_of("feature-drift").trim(), "submodules: 1 drifted (lib)", "Actual output: {}", stdout);
+    assert_eq!(submodules_of("feature-dirty").trim(), "submodules: 1 dirty (lib)", "Actual output: {}", stdout);
+    assert!(!submodules_of("main").contains("submodules"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_remove_worktree_with_submodules() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-remove", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    let worktree = helper.worktree_path("feature-remove");
+    assert!(worktree.join("lib/lib.txt").exists());
+    
+    let output = helper.run_gwtr(&["remove", "feature-remove"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree.exists());
+}
+
+#[test]
+fn test_remove_refuses_dirty_worktree_with_submodules() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-dirty", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    let worktree = helper.worktree_path("feature-dirty");
+    std::fs::write(worktree.join("notes.txt"), "keep me").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-dirty"]);
+    assert!(!output.status.success());
+    assert!(worktree.join("notes.txt").exists());
+}
+
+#[test]
+fn test_remove_force_refuses_dirty_submodules_and_restores_the_rest() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-dirty", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    let worktree = helper.worktree_path("feature-dirty");
+    std::fs::write(worktree.join("notes.txt"), "keep me").unwrap();
+    std::fs::write(worktree.join("lib/lib.txt"), "changed").unwrap();
+    
+    // The trash cannot hold the submodule's changes, so even --force keeps the worktree
+    let output = helper.run_gwtr(&["remove", "feature-dirty", "--force"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("submodule(s) lib"), "Actual stderr: {}", stderr);
+    assert_eq!(std::fs::read_to_string(worktree.join("lib/lib.txt")).unwrap(), "changed");
+    assert!(helper.git(&["for-each-ref", "refs/gwtr/trash/"]).is_empty());
+    
+    helper.git_in(&worktree.join("lib"), &["checkout", "--", "lib.txt"]);
+    let output = helper.run_gwtr(&["remove", "feature-dirty", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree.exists());
+    
+    let output = helper.run_gwtr(&["restore", "feature-dirty"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(std::fs::read_to_string(worktree.join("notes.txt")).unwrap(), "keep me");
+}
+
+#[test]
+fn test_undo_add_of_worktree_with_submodules() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-sub", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!helper.worktree_path("feature-sub").exists());
+    assert!(helper.git(&["branch", "--list", "feature-sub"]).is_empty());
+}
+
+#[test]
+fn test_rename_worktree_with_submodules() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-old", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    
+    let output = helper.run_gwtr(&["rename", "feature-old", "feature-new"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let worktree = helper.worktree_path("feature-new");
+    assert!(!helper.worktree_path("feature-old").exists());
+    assert_eq!(std::fs::read_to_string(worktree.join("lib/lib.txt")).unwrap(), "library");
+    
+    // Both the worktree and its submodule still work from the new location
+    assert_eq!(helper.git_in(&worktree, &["status", "--porcelain"]), "");
+    assert_eq!(helper.git_in(&worktree.join("lib"), &["status", "--porcelain"]), "");
+    let list = helper.git(&["worktree", "list"]);
+    assert!(list.contains(worktree.to_str().unwrap()), "Worktrees: {}", list);
+    
+    let output = helper.run_gwtr(&["remove", "feature-new"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_prune_worktrees_with_submodules() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    helper.run_gwtr_with_env(&["add", "feature-a", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    helper.run_gwtr_with_env(&["add", "feature-b", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Pruned 2 worktrees"), "Actual output: {}", stdout);
+    assert!(!helper.worktree_path("feature-a").exists());
+    assert!(!helper.worktree_path("feature-b").exists());
+}
+
+#[test]
+fn test_add_submodules_never_borrows_from_linked_worktrees() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    
+    // A clone whose main worktree never initialised the submodule
+    let clone = TestHelper::new().unwrap();
+    std::fs::remove_dir_all(clone.repo_path.join(".git")).unwrap();
+    helper.git(&["clone", ".", clone.repo_path.to_str().unwrap()]);
+    clone.run_gwtr_with_env(&["add", "feature-first", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    
+    let output = clone.run_gwtr_with_env(&["add", "feature-second", "--submodules"], &ALLOW_FILE_PROTOCOL);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Initialised 1 submodule(s), 0 using objects from existing clones"), "Actual output: {}", stdout);
+    
+    // The second clone keeps working once the first worktree is gone
+    clone.run_gwtr(&["remove", "feature-first"]);
+    let submodule = clone.worktree_path("feature-second").join("lib");
+    clone.git_in(&submodule, &["fsck", "--connectivity-only"]);
+    clone.git_in(&submodule, &["log", "-1"]);
+}
+
+#[test]
+fn test_add_inside_submodule_places_worktree_next_to_it() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let library = tempfile::TempDir::new().unwrap();
+    add_submodule(&helper, library.path());
+    let submodule = helper.repo_path.join("lib");
+    helper.git_in(&submodule, &["config", "user.email", "test@example.com"]);
+    helper.git_in(&submodule, &["config", "user.name", "Test User"]);
+    
+    let output = helper.run_gwtr_in(&submodule, &["add", "feature-lib"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let worktree = helper.repo_path.join("lib_feature-lib");
+    assert!(worktree.join("lib.txt").exists(), "Actual output: {}", String::from_utf8_lossy(&output.stdout));
+    assert!(!helper.repo_path.join(".git/modules_feature-lib").exists());
+    
+    // Commands run inside the new worktree find the submodule's main worktree too
+    let output = helper.run_gwtr_in(&worktree, &["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(submodule.to_str().unwrap()), "Actual output: {}", stdout);
+    assert!(stdout.contains("[feature-lib]"), "Actual output: {}", stdout);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::TestHelper;
+
+#[test]
+fn test_tmp_creates_auto_named_temporary_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["tmp", "--ttl", "2d"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Temporary worktree 'tmp-1' expires in 2d"), "Actual output: {}", stdout);
+    assert!(helper.worktree_path("tmp-1").exists());
+    
+    let output = helper.run_gwtr(&["tmp"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Temporary worktree 'tmp-2' expires in 1d"), "Actual output: {}", stdout);
+    
+    let metadata = std::fs::read_to_string(helper.repo_path.join(".git/gwtr/worktrees.json")).unwrap();
+    assert!(metadata.contains("\"expires_at\""), "Metadata: {}", metadata);
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|line| line.contains("[tmp-1]")).unwrap();
+    assert!(line.contains("temporary: expires in"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_tmp_from_ref() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.git(&["tag", "v1.0"]);
+    helper.commit_file(&helper.repo_path, "later.txt", "later");
+    
+    let output = helper.run_gwtr(&["tmp", "--from", "v1.0"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!helper.worktree_path("tmp-1").join("later.txt").exists());
+    assert_eq!(helper.git(&["rev-parse", "tmp-1"]), helper.git(&["rev-parse", "v1.0"]));
+}
+
+#[test]
+fn test_any_invocation_removes_expired_clean_temporaries() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["tmp", "--ttl", "2d"]);
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    
+    // tmp-2 is dirty, so it is only warned about
+    std::fs::write(helper.worktree_path("tmp-2").join("notes.txt"), "keep me").unwrap();
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    
+    let output = helper.run_gwtr(&["list"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Removed temporary worktree 'tmp-3'"), "stderr: {}", stderr);
+    assert!(!stdout.contains("Removed temporary worktree"), "Actual output: {}", stdout);
+    assert!(stderr.contains("temporary worktree 'tmp-2' expired"), "stderr: {}", stderr);
+    assert!(stderr.contains("1 uncommitted changes"), "stderr: {}", stderr);
+    
+    assert!(!helper.worktree_path("tmp-3").exists());
+    assert!(helper.git(&["branch", "--list", "tmp-3"]).is_empty());
+    assert!(helper.worktree_path("tmp-2").join("notes.txt").exists());
+    assert!(helper.worktree_path("tmp-1").exists());
+}
+
+#[test]
+fn test_expiry_keeps_branch_with_own_commits() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    helper.commit_file(&helper.worktree_path("tmp-1"), "experiment.txt", "result");
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Kept branch 'tmp-1'"), "stderr: {}", stderr);
+    assert!(!helper.worktree_path("tmp-1").exists());
+    assert!(!helper.git(&["branch", "--list", "tmp-1"]).is_empty());
+}
+
+#[test]
+fn test_expiry_keeps_env_output_clean() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["add", "feature-x"]);
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    
+    // `gwtr env` output is meant for eval, so cleanup reports go to stderr
+    let output = helper.run_gwtr(&["env", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Removed temporary worktree 'tmp-1'"), "stderr: {}", stderr);
+    assert!(stdout.lines().all(|line| line.contains('=')), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_undo_and_log_do_not_expire_temporaries() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    
+    let output = helper.run_gwtr(&["log"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Removed temporary worktree"), "stderr: {}", stderr);
+    assert!(helper.worktree_path("tmp-1").exists());
+    
+    // Undo reverses the user's last action rather than the automatic cleanup
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Removed temporary worktree"), "stderr: {}", stderr);
+    assert!(!helper.worktree_path("tmp-1").exists());
+    assert!(helper.git(&["branch", "--list", "tmp-1"]).is_empty());
+}
+
+#[test]
+fn test_prune_expired() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.run_gwtr(&["tmp", "--ttl", "0s"]);
+    let worktree = helper.worktree_path("tmp-1");
+    std::fs::write(worktree.join("notes.txt"), "scratch").unwrap();
+    
+    let output = helper.run_gwtr(&["prune", "--expired", "--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipping"), "Actual output: {}", stdout);
+    assert!(stdout.contains("uncommitted changes (use --include-dirty to prune it)"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["prune", "--expired", "--include-dirty", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(temporary, expired"), "Actual output: {}", stdout);
+    assert!(!worktree.exists());
+    
+    // The uncommitted work is kept in the trash
+    let output = helper.run_gwtr(&["trash", "list"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("tmp-1/"));
+}
+
+#[test]
+fn test_tmp_rejects_too_large_ttl() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    for ttl in ["18446744073709551615s", "10000000000000000000s"] {
+        let output = helper.run_gwtr(&["tmp", "--ttl", ttl]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("duration too large"), "stderr: {}", stderr);
+        assert!(!stderr.contains("panicked"), "stderr: {}", stderr);
+    }
+    assert!(!helper.worktree_path("tmp-1").exists());
+}
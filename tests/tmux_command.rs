@@ -0,0 +1,140 @@
+mod common;
+
+use common::TestHelper;
+use std::process::Command;
+
+/// A private tmux server for one test, killed when the test ends
+struct TmuxServer {
+    socket: String,
+}
+
+impl TmuxServer {
+    fn new(helper: &TestHelper, test: &str, windows: &str) -> Self {
+        let socket = format!("gwtr-test-{}-{}", std::process::id(), test);
+        std::fs::write(
+            helper.repo_path.join(".gwtr.toml"),
+            format!("[tmux]\nsocket = \"{}\"\n{}", socket, windows),
+        ).unwrap();
+        TmuxServer { socket }
+    }
+    
+    fn tmux(&self, args: &[&str]) -> (bool, String) {
+        let output = Command::new("tmux")
+            .args(["-L", &self.socket])
+            .args(args)
+            .output()
+            .expect("Failed to execute tmux");
+        (output.status.success(), String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+impl Drop for TmuxServer {
+    fn drop(&mut self) {
+        let _ = self.tmux(&["kill-server"]);
+    }
+}
+
+fn session_name(helper: &TestHelper, name: &str) -> String {
+    let repo = helper.repo_path.file_name().unwrap().to_str().unwrap();
+    format!("{}/{}", repo, name).replace('.', "_")
+}
+
+#[test]
+fn test_tmux_creates_session_in_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let server = TmuxServer::new(&helper, "create", "");
+    helper.run_gwtr(&["add", "feature-tmux"]);
+    
+    let output = helper.run_gwtr(&["tmux", "feature-tmux"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let session = session_name(&helper, "feature-tmux");
+    assert!(stdout.contains(&format!("Created tmux session '{}'", session)), "Actual output: {}", stdout);
+    
+    // Outside a terminal the hint names the configured server
+    let hint = format!("Attach with: tmux -L {} attach -t '{}'", server.socket, session);
+    assert!(stdout.contains(&hint), "Actual output: {}", stdout);
+    
+    let (exists, path) = server.tmux(&["display-message", "-p", "-t", &format!("={}:", session), "#{pane_current_path}"]);
+    assert!(exists);
+    let worktree = helper.worktree_path("feature-tmux").canonicalize().unwrap();
+    assert_eq!(std::path::PathBuf::from(path).canonicalize().unwrap(), worktree);
+    
+    // A second call reuses the session
+    let output = helper.run_gwtr(&["tmux", "feature-tmux"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Using tmux session"), "Actual output: {}", stdout);
+    let (_, sessions) = server.tmux(&["list-sessions", "-F", "#{session_name}"]);
+    assert_eq!(sessions.lines().count(), 1);
+}
+
+#[test]
+fn test_tmux_creates_configured_windows_and_panes() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let server = TmuxServer::new(&helper, "windows", r#"
+[[tmux.windows]]
+name = "editor"
+
+[[tmux.windows]]
+name = "servers"
+panes = ["true", "true"]
+layout = "tiled"
+"#);
+    
+    let output = helper.run_gwtr(&["add", "feature-layout", "--tmux"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let session = session_name(&helper, "feature-layout");
+    let (_, windows) = server.tmux(&["list-windows", "-t", &format!("={}", session), "-F", "#{window_name} #{window_panes}"]);
+    assert_eq!(windows.lines().collect::<Vec<_>>(), ["editor 1", "servers 3"]);
+}
+
+#[test]
+fn test_tmux_session_has_worktree_environment() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let _server = TmuxServer::new(&helper, "env", r#"
+[[tmux.windows]]
+command = "echo $GWTR_WORKTREE $GWTR_PORT > env.txt"
+"#);
+    
+    let output = helper.run_gwtr(&["add", "feature-env", "--tmux"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    // The command is typed into the pane's shell, which runs it asynchronously
+    let env_file = helper.worktree_path("feature-env").join("env.txt");
+    for _ in 0..50 {
+        if std::fs::read_to_string(&env_file).is_ok_and(|content| content.ends_with('\n')) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(std::fs::read_to_string(&env_file).unwrap(), "feature-env 4000\n");
+}
+
+#[test]
+fn test_remove_and_prune_kill_session() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    let server = TmuxServer::new(&helper, "kill", "");
+    helper.run_gwtr(&["add", "feature-removed"]);
+    helper.run_gwtr(&["add", "feature-pruned"]);
+    helper.run_gwtr(&["add", "feature-kept"]);
+    for name in ["feature-removed", "feature-pruned", "feature-kept"] {
+        helper.run_gwtr(&["tmux", name]);
+    }
+    
+    let output = helper.run_gwtr(&["remove", "feature-removed"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Killed tmux session"), "Actual output: {}", stdout);
+    
+    // feature-kept has a commit, so it is not merged
+    helper.commit_file(&helper.worktree_path("feature-kept"), "kept.txt", "kept");
+    let output = helper.run_gwtr(&["prune", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    
+    let (_, sessions) = server.tmux(&["list-sessions", "-F", "#{session_name}"]);
+    assert_eq!(sessions, session_name(&helper, "feature-kept"));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::TestHelper;
+use std::fs;
+
+#[test]
+fn test_remove_saves_uncommitted_work_to_trash() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    helper.commit_file(&helper.repo_path, "a.txt", "original");
+    
+    helper.run_gwtr(&["add", "feature-wip"]);
+    let worktree_path = helper.worktree_path("feature-wip");
+    fs::write(worktree_path.join("README.md"), "staged change").unwrap();
+    helper.git_in(&worktree_path, &["add", "README.md"]);
+    fs::write(worktree_path.join("a.txt"), "unstaged change").unwrap();
+    fs::write(worktree_path.join("new.txt"), "untracked file").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-wip", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("refs/gwtr/trash/feature-wip/"), "Actual output: {}", stdout);
+    assert!(!worktree_path.exists());
+    
+    let output = helper.run_gwtr(&["trash", "list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("feature-wip/"), "Actual output: {}", stdout);
+    assert!(stdout.contains("[feature-wip]"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["restore", "feature-wip"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree_path.exists());
+    
+    // Staged, unstaged and untracked changes come back as they were
+    let status = helper.git_in(&worktree_path, &["status", "--porcelain"]);
+    assert!(status.contains("M  README.md"), "Actual status: {}", status);
+    assert!(status.contains(" M a.txt"), "Actual status: {}", status);
+    assert!(status.contains("?? new.txt"), "Actual status: {}", status);
+    assert_eq!(helper.git_in(&worktree_path, &["branch", "--show-current"]), "feature-wip");
+    
+    // The restored entry is removed from the trash
+    let output = helper.run_gwtr(&["trash", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Trash is empty"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_trash_keeps_every_entry_for_the_same_name() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    // Entries saved within the same second must not replace each other
+    for content in ["first", "second"] {
+        helper.run_gwtr(&["add", "feature-wip"]);
+        fs::write(helper.worktree_path("feature-wip").join("notes.txt"), content).unwrap();
+        let output = helper.run_gwtr(&["remove", "feature-wip", "--force"]);
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    
+    let refs = helper.git(&["for-each-ref", "--format=%(refname)", "refs/gwtr/trash/feature-wip/"]);
+    assert_eq!(refs.lines().count(), 2, "Trash refs: {}", refs);
+    let output = helper.run_gwtr(&["trash", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("feature-wip/").count(), 2, "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_restore_recreates_deleted_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-gone"]);
+    let worktree_path = helper.worktree_path("feature-gone");
+    helper.commit_file(&worktree_path, "work.txt", "committed");
+    fs::write(worktree_path.join("work.txt"), "uncommitted").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-gone", "--force", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git(&["branch", "--list", "feature-gone"]).is_empty());
+    
+    let output = helper.run_gwtr(&["restore", "feature-gone"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(worktree_path.join("work.txt")).unwrap(), "uncommitted");
+    assert_eq!(helper.git_in(&worktree_path, &["log", "-1", "--format=%s"]), "Update work.txt");
+}
+
+#[test]
+fn test_clean_remove_does_not_use_trash() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-clean"]);
+    let output = helper.run_gwtr(&["remove", "feature-clean"]);
+    assert!(output.status.success());
+    
+    let output = helper.run_gwtr(&["trash", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Trash is empty"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_prune_saves_dirty_worktrees_and_trash_drop() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-merged"]);
+    fs::write(helper.worktree_path("feature-merged").join("scratch.txt"), "notes").unwrap();
+    
+    let output = helper.run_gwtr(&["prune", "--include-dirty", "--force"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("refs/gwtr/trash/feature-merged/"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["trash", "drop", "feature-merged"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.git(&["for-each-ref", "refs/gwtr/trash/"]).is_empty());
+    
+    let output = helper.run_gwtr(&["restore", "feature-merged"]);
+    assert!(!output.status.success());
+}
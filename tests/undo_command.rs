@@ -0,0 +1,145 @@
+mod common;
+
+use common::TestHelper;
+use std::fs;
+
+#[test]
+fn test_log_records_operations() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-a"]);
+    helper.run_gwtr(&["remove", "feature-a"]);
+    
+    let output = helper.run_gwtr(&["log"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "Actual output: {}", stdout);
+    assert!(lines[0].contains("#2") && lines[0].contains("remove") && lines[0].contains("feature-a"), "Actual output: {}", stdout);
+    assert!(lines[1].contains("#1") && lines[1].contains("add"), "Actual output: {}", stdout);
+    
+    // The journal lives in the common git dir
+    let journal = fs::read_to_string(helper.repo_path.join(".git/gwtr/journal.jsonl")).unwrap();
+    assert_eq!(journal.lines().count(), 2);
+}
+
+#[test]
+fn test_undo_add_removes_worktree_and_branch() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-oops"]);
+    assert!(helper.worktree_path("feature-oops").exists());
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!helper.worktree_path("feature-oops").exists());
+    assert!(helper.git(&["branch", "--list", "feature-oops"]).is_empty());
+    
+    let output = helper.run_gwtr(&["log"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("undo #1"), "Actual output: {}", stdout);
+    assert!(stdout.contains("(undone)"), "Actual output: {}", stdout);
+    
+    // Undo entries themselves are not undone
+    let output = helper.run_gwtr(&["undo"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Nothing to undo"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_undo_remove_recreates_worktree() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    helper.run_gwtr(&["add", "feature-x"]);
+    let worktree_path = helper.worktree_path("feature-x");
+    helper.commit_file(&worktree_path, "x.txt", "committed");
+    fs::write(worktree_path.join("x.txt"), "uncommitted").unwrap();
+    
+    let output = helper.run_gwtr(&["remove", "feature-x", "--force", "--delete-branch"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!worktree_path.exists());
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(worktree_path.exists());
+    assert_eq!(fs::read_to_string(worktree_path.join("x.txt")).unwrap(), "uncommitted");
+    assert_eq!(helper.git_in(&worktree_path, &["branch", "--show-current"]), "feature-x");
+}
+
+#[test]
+fn test_undo_remove_restores_metadata() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    for dir in ["services/api", "services/web"] {
+        fs::create_dir_all(helper.repo_path.join(dir)).unwrap();
+        fs::write(helper.repo_path.join(dir).join("file.txt"), dir).unwrap();
+    }
+    helper.git(&["add", "."]);
+    helper.git(&["commit", "-m", "Add services"]);
+    fs::write(helper.repo_path.join(".gwtr.toml"), "[sparse_profiles]\napi = [\"services/api\"]\n").unwrap();
+    
+    helper.run_gwtr(&["add", "feature-first"]);
+    helper.run_gwtr(&["add", "feature-api", "--sparse", "api"]);
+    helper.run_gwtr(&["remove", "feature-first"]);
+    helper.run_gwtr(&["remove", "feature-api"]);
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let worktree_path = helper.worktree_path("feature-api");
+    assert!(worktree_path.join("services/api/file.txt").exists());
+    assert!(!worktree_path.join("services/web").exists());
+    
+    let output = helper.run_gwtr(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sparse: api (services/api)"), "Actual output: {}", stdout);
+    let output = helper.run_gwtr(&["env", "feature-api"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("GWTR_PORT=4010"), "Actual output: {}", stdout);
+}
+
+#[test]
+fn test_undo_pull_resets_to_previous_commit() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let remote_dir = tempfile::TempDir::new().unwrap();
+    helper.git_in(remote_dir.path(), &["init", "--bare"]);
+    helper.git(&["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+    helper.git(&["push", "-u", "origin", "main"]);
+    let before = helper.git(&["rev-parse", "HEAD"]);
+    
+    // Someone else pushes a new commit
+    let other_dir = tempfile::TempDir::new().unwrap();
+    let other_clone = other_dir.path().join("clone");
+    helper.git_in(other_dir.path(), &["clone", "-b", "main", remote_dir.path().to_str().unwrap(), "clone"]);
+    helper.git_in(&other_clone, &["config", "user.email", "other@example.com"]);
+    helper.git_in(&other_clone, &["config", "user.name", "Other"]);
+    helper.commit_file(&other_clone, "upstream.txt", "upstream");
+    helper.git_in(&other_clone, &["push", "origin", "main"]);
+    
+    let output = helper.run_gwtr(&["pull"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_ne!(helper.git(&["rev-parse", "HEAD"]), before);
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(helper.git(&["rev-parse", "HEAD"]), before);
+}
+
+#[test]
+fn test_undo_with_empty_journal() {
+    let helper = TestHelper::new().unwrap();
+    helper.init_main();
+    
+    let output = helper.run_gwtr(&["undo"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Nothing to undo"), "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr(&["log"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No operations recorded"), "Actual output: {}", stdout);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::TestHelper;
+use std::path::{Path, PathBuf};
+
+/// Create repositories `api` and `web` next to a workspace manifest listing them
+fn init_workspace(helper: &TestHelper) -> (PathBuf, Vec<PathBuf>) {
+    let root = helper.repo_path.join("workspace");
+    let mut repos = Vec::new();
+    for name in ["api", "web"] {
+        let repo = root.join(name);
+        std::fs::create_dir_all(&repo).unwrap();
+        helper.git_in(&repo, &["init", "-b", "main"]);
+        helper.git_in(&repo, &["config", "user.email", "test@example.com"]);
+        helper.git_in(&repo, &["config", "user.name", "Test User"]);
+        helper.commit_file(&repo, "README.md", name);
+        repos.push(repo);
+    }
+    std::fs::write(root.join("gwtr-workspace.toml"), "repos = [\"api\", \"web\"]\n").unwrap();
+    (root, repos)
+}
+
+fn worktree(repo: &Path, name: &str) -> PathBuf {
+    let repo_name = repo.file_name().unwrap().to_str().unwrap();
+    repo.parent().unwrap().join(format!("{}_{}", repo_name, name))
+}
+
+#[test]
+fn test_ws_add_status_and_remove() {
+    let helper = TestHelper::new().unwrap();
+    let (root, repos) = init_workspace(&helper);
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    for repo in &repos {
+        let branch = helper.git_in(&worktree(repo, "feature-x"), &["branch", "--show-current"]);
+        assert_eq!(branch, "feature-x");
+    }
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "status"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("api:") && stdout.contains("web:"), "Actual output: {}", stdout);
+    assert_eq!(stdout.matches("[feature-x] - clean").count(), 2, "Actual output: {}", stdout);
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "remove", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    for repo in &repos {
+        assert!(!worktree(repo, "feature-x").exists());
+    }
+}
+
+#[test]
+fn test_ws_add_rolls_back_when_one_repo_fails() {
+    let helper = TestHelper::new().unwrap();
+    let (root, repos) = init_workspace(&helper);
+    
+    // The branch is already checked out in web, so creating its worktree fails
+    helper.git_in(&repos[1], &["checkout", "-b", "feature-x"]);
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "add", "feature-x"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no worktrees were created"), "stderr: {}", stderr);
+    
+    assert!(!worktree(&repos[0], "feature-x").exists());
+    let branches = helper.git_in(&repos[0], &["branch", "--list", "feature-x"]);
+    assert!(branches.is_empty(), "Branches: {}", branches);
+}
+
+#[test]
+fn test_ws_remove_refuses_when_one_repo_is_dirty() {
+    let helper = TestHelper::new().unwrap();
+    let (root, repos) = init_workspace(&helper);
+    helper.run_gwtr_in(&root, &["ws", "add", "feature-x"]);
+    std::fs::write(worktree(&repos[1], "feature-x").join("scratch.txt"), "work").unwrap();
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "remove", "feature-x"]);
+    assert!(!output.status.success());
+    for repo in &repos {
+        assert!(worktree(repo, "feature-x").exists());
+    }
+}
+
+#[test]
+fn test_ws_remove_rolls_back_repo_that_failed_after_removing() {
+    let helper = TestHelper::new().unwrap();
+    let (root, repos) = init_workspace(&helper);
+    helper.run_gwtr_in(&root, &["ws", "add", "feature-x"]);
+    
+    // web deletes the remote branch too, which fails since the remote does not exist
+    std::fs::write(repos[1].join(".gwtr.toml"), "delete_branch = true\ndelete_remote_branch = true\n").unwrap();
+    helper.git_in(&repos[1], &["config", "branch.feature-x.remote", "origin"]);
+    helper.git_in(&repos[1], &["config", "branch.feature-x.merge", "refs/heads/feature-x"]);
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "remove", "feature-x"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no worktrees were removed"), "stderr: {}", stderr);
+    
+    for repo in &repos {
+        assert!(worktree(repo, "feature-x").exists(), "{:?} was not restored", repo);
+        let branches = helper.git_in(repo, &["branch", "--list", "feature-x"]);
+        assert!(!branches.is_empty(), "{:?} lost its branch", repo);
+    }
+}
+
+#[test]
+fn test_ws_prune_requires_merge_in_every_repo() {
+    let helper = TestHelper::new().unwrap();
+    let (root, repos) = init_workspace(&helper);
+    helper.run_gwtr_in(&root, &["ws", "add", "feature-x"]);
+    helper.run_gwtr_in(&root, &["ws", "add", "feature-y"]);
+    
+    // feature-y has unmerged work in api
+    helper.commit_file(&worktree(&repos[0], "feature-y"), "y.txt", "y");
+    
+    let output = helper.run_gwtr_in(&root, &["ws", "prune", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Pruned 1 feature(s)"), "Actual output: {}", stdout);
+    
+    for repo in &repos {
+        assert!(!worktree(repo, "feature-x").exists());
+        assert!(worktree(repo, "feature-y").exists());
+    }
+}
+
+#[test]
+fn test_ws_requires_manifest() {
+    let helper = TestHelper::new().unwrap();
+    
+    let output = helper.run_gwtr(&["ws", "status"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("gwtr-workspace.toml"), "stderr: {}", stderr);
+}
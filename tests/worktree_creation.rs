@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 mod common;
 
 use common::TestHelper;
@@ -122,4 +124,20 @@ fn test_add_fails_if_worktree_already_exists() {
     let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(stderr.contains("already exists") || stderr.contains("already"), 
             "Error message should indicate worktree already exists: {}", stderr);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_add_with_separate_git_dir() {
+    let helper = TestHelper::new().unwrap();
+    let git_dir = tempfile::TempDir::new().unwrap();
+    let checkout = helper.repo_path.join("app");
+    let separate = git_dir.path().join("app.git");
+    helper.git(&["init", "-b", "main", "--separate-git-dir", separate.to_str().unwrap(), "app"]);
+    helper.git_in(&checkout, &["config", "user.email", "test@example.com"]);
+    helper.git_in(&checkout, &["config", "user.name", "Test User"]);
+    helper.commit_file(&checkout, "README.md", "Initial");
+    
+    let output = helper.run_gwtr_in(&checkout, &["add", "feature-x"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(helper.repo_path.join("app_feature-x").join("README.md").exists());
+}